@@ -1,7 +1,8 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion};
 use glob::Pattern;
 use regex::Regex;
 use regex_lite::Regex as RegexLite;
+use std::hint::black_box;
 use wildmatch::WildMatch;
 
 const TEXT: &str = "Lorem ipsum dolor sit amet, \
@@ -22,6 +23,12 @@ Lorem ipsum dolor sit amet\\.$";
 const COMPLEX_PATTERN: &str = "Lorem?ipsum*dolore*ea* ?????ata*.";
 const COMPLEX_REGEX: &str = "^Lorem.ipsum.*dolore.*ea.* .....ata.*\\.$";
 
+/// Short patterns which are matched with the bit-parallel shift-and matcher:
+/// case-insensitive patterns and case-sensitive patterns without literals.
+const SHORT_PATTERN: &str = "*a?c*";
+const SHORT_REGEX: &str = "(?is)^.*a.c.*$";
+const WILDCARDS_PATTERN: &str = "*?*?";
+
 const MOST_COMPLEX_PATTERN: &str = "?a*b*?**c?d****?e*f*g*?*h?i*?*?**j*******k";
 const MOST_COMPLEX_REGEX: &str =
     "^.a.*b.*..*.*c.d.*.*.*.*.e.*f.*g.*..*h.i.*..*..*.*j.*.*.*.*.*.*.*k$";
@@ -61,6 +68,10 @@ pub fn compiling(c: &mut Criterion) {
 pub fn matching(c: &mut Criterion) {
     let pattern1 = WildMatch::new(FULL_TEXT_PATTERN);
     let pattern2 = WildMatch::new(COMPLEX_PATTERN);
+    let pattern3 = WildMatch::new(MOST_COMPLEX_PATTERN);
+    let wildcards = WildMatch::new(WILDCARDS_PATTERN);
+    let nfa1 = pattern1.to_nfa();
+    let nfa2 = pattern2.to_nfa();
     let regex1 = Regex::new(FULL_TEXT_REGEX).unwrap();
     let regex2 = Regex::new(COMPLEX_REGEX).unwrap();
     let glob1 = Pattern::new(FULL_TEXT_PATTERN).unwrap();
@@ -77,6 +88,29 @@ pub fn matching(c: &mut Criterion) {
         b.iter(|| pattern2 == black_box(TEXT))
     });
 
+    group.bench_function("match complex (wildmatch tokens)", |b| {
+        b.iter(|| pattern2.matches_bytes(black_box(TEXT.as_bytes())))
+    });
+    group.bench_function("match most complex (wildmatch)", |b| {
+        b.iter(|| pattern3.matches(black_box(TEXT)))
+    });
+    group.bench_function("match most complex (wildmatch tokens)", |b| {
+        b.iter(|| pattern3.matches_bytes(black_box(TEXT.as_bytes())))
+    });
+    group.bench_function("match wildcards (wildmatch shift-and)", |b| {
+        b.iter(|| wildcards.matches(black_box(TEXT)))
+    });
+    group.bench_function("match wildcards (wildmatch tokens)", |b| {
+        b.iter(|| wildcards.matches_bytes(black_box(TEXT.as_bytes())))
+    });
+
+    group.bench_function("match text (wildmatch nfa)", |b| {
+        b.iter(|| nfa1.matches(black_box(TEXT)))
+    });
+    group.bench_function("match complex (wildmatch nfa)", |b| {
+        b.iter(|| nfa2.matches(black_box(TEXT)))
+    });
+
     group.bench_function("match text (regex)", |b| {
         b.iter(|| regex1.is_match(black_box(TEXT)))
     });
@@ -99,5 +133,71 @@ pub fn matching(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, compiling, matching);
+pub fn matching_case_insensitive(c: &mut Criterion) {
+    let text = TEXT.to_uppercase();
+    let pattern1 = WildMatch::new_case_insensitive(FULL_TEXT_PATTERN);
+    let pattern2 = WildMatch::new_case_insensitive(COMPLEX_PATTERN);
+    let short = WildMatch::new_case_insensitive(SHORT_PATTERN);
+    let regex1 = Regex::new(&format!("(?i){}", FULL_TEXT_REGEX)).unwrap();
+    let regex2 = Regex::new(&format!("(?i){}", COMPLEX_REGEX)).unwrap();
+    let short_regex = Regex::new(SHORT_REGEX).unwrap();
+
+    let mut group = c.benchmark_group("matching case-insensitive");
+
+    group.bench_function("match text (wildmatch)", |b| {
+        b.iter(|| pattern1.matches(black_box(&text)))
+    });
+    group.bench_function("match complex (wildmatch)", |b| {
+        b.iter(|| pattern2.matches(black_box(&text)))
+    });
+    group.bench_function("match short (wildmatch shift-and)", |b| {
+        b.iter(|| short.matches(black_box(&text)))
+    });
+    group.bench_function("match short (wildmatch tokens)", |b| {
+        b.iter(|| short.matches_bytes(black_box(text.as_bytes())))
+    });
+
+    group.bench_function("match text (regex)", |b| {
+        b.iter(|| regex1.is_match(black_box(&text)))
+    });
+    group.bench_function("match complex (regex)", |b| {
+        b.iter(|| regex2.is_match(black_box(&text)))
+    });
+    group.bench_function("match short (regex)", |b| {
+        b.iter(|| short_regex.is_match(black_box(&text)))
+    });
+}
+
+pub fn matching_many(c: &mut Criterion) {
+    let lines: Vec<String> = (0..10_000)
+        .map(|i| match i % 100 {
+            0 => format!("2024-01-01 12:{:02} ERROR request {} timed out", i % 60, i),
+            _ => format!("2024-01-01 12:{:02} INFO request {} served", i % 60, i),
+        })
+        .collect();
+    let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let pattern = WildMatch::new("* ERROR request * timed out");
+
+    let mut group = c.benchmark_group("matching many");
+
+    group.bench_function("match lines (wildmatch)", |b| {
+        b.iter(|| {
+            black_box(&lines)
+                .iter()
+                .map(|line| pattern.matches(line))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("match lines batched (wildmatch)", |b| {
+        b.iter(|| pattern.matches_many(black_box(&lines)))
+    });
+}
+
+criterion_group!(
+    benches,
+    compiling,
+    matching,
+    matching_case_insensitive,
+    matching_many
+);
 criterion_main!(benches);
@@ -0,0 +1,360 @@
+//! Building patterns from a combination of options.
+
+use crate::parse::Syntax;
+use crate::{PatternError, WildMatchPattern};
+
+/// A builder for patterns using `*` as the multi-character wildcard and `?`
+/// as the single-character wildcard.
+pub type WildMatchBuilder = WildMatchPatternBuilder<'*', '?'>;
+
+/// A builder which collects options and builds [`WildMatchPattern`]s with them.
+///
+/// By default a built pattern behaves like one created with
+/// [`WildMatchPattern::new`]. Unlike the constructors, building fails with a
+/// [`PatternError`] if the pattern contains invalid syntax.
+///
+/// ```
+/// # use wildmatch::WildMatchBuilder;
+/// let builder = WildMatchBuilder::new()
+///     .case_insensitive(true)
+///     .separators(&['/'])
+///     .literal_leading_dot(true);
+///
+/// let m = builder.build("src/**/*.RS").unwrap();
+/// assert!(m.matches("src/a/lib.rs"));
+/// assert!(!m.matches("src/.a/lib.rs"));
+///
+/// let m = builder.clone().anchor_start(false).build("*.rs").unwrap();
+/// assert!(m.matches("src/lib.rs"));
+///
+/// assert!(builder.classes(true).build("[a-z").is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WildMatchPatternBuilder<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> {
+    case_insensitive: bool,
+    syntax: Syntax,
+    max_len: Option<usize>,
+}
+
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char>
+    WildMatchPatternBuilder<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+    /// Constructor with the default options.
+    pub fn new() -> WildMatchPatternBuilder<MULTI_WILDCARD, SINGLE_WILDCARD> {
+        Self::default()
+    }
+
+    /// Sets whether characters are compared case-insensitively.
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Sets the character which makes the following character match literally.
+    /// See [`WildMatchPattern::new_with_escape`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `escape` is one of the wildcard characters.
+    pub fn escape(mut self, escape: char) -> Self {
+        assert!(
+            escape != MULTI_WILDCARD && escape != SINGLE_WILDCARD,
+            "escape character cannot be a wildcard"
+        );
+        self.syntax.escape = Some(escape);
+        self
+    }
+
+    /// Sets whether `[...]` denotes a character class. See
+    /// [`WildMatchPattern::new_with_classes`].
+    pub fn classes(mut self, classes: bool) -> Self {
+        self.syntax.classes = classes;
+        self
+    }
+
+    /// Sets whether `{a,b}` denotes an alternation. See
+    /// [`WildMatchPattern::new_with_braces`].
+    pub fn braces(mut self, braces: bool) -> Self {
+        self.syntax.braces = braces;
+        self
+    }
+
+    /// Sets the path separators. Patterns are matched in path mode if there
+    /// are any. See [`WildMatchPattern::new_with_separators`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the `separators` is a wildcard character.
+    pub fn separators(mut self, separators: &[char]) -> Self {
+        assert!(
+            !separators.contains(&MULTI_WILDCARD) && !separators.contains(&SINGLE_WILDCARD),
+            "path separator cannot be a wildcard"
+        );
+        self.syntax.separators = separators.to_vec();
+        self
+    }
+
+    /// Sets whether a `.` at the start of the input, or in path mode at the
+    /// start of a path segment, is only matched by a literal `.` in the pattern
+    /// and never by a wildcard or character class.
+    pub fn literal_leading_dot(mut self, literal_leading_dot: bool) -> Self {
+        self.syntax.literal_leading_dot = literal_leading_dot;
+        self
+    }
+
+    /// Sets whether matches have to start at the start of the input. If not,
+    /// the pattern may match any suffix of the input.
+    pub fn anchor_start(mut self, anchored: bool) -> Self {
+        self.syntax.unanchored_start = !anchored;
+        self
+    }
+
+    /// Sets whether matches have to end at the end of the input. If not, the
+    /// pattern may match any prefix of the input.
+    pub fn anchor_end(mut self, anchored: bool) -> Self {
+        self.syntax.unanchored_end = !anchored;
+        self
+    }
+
+    /// Sets the maximum number of characters of a pattern.
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Builds a pattern with the options of the builder.
+    pub fn build(
+        &self,
+        pattern: &str,
+    ) -> Result<WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>, PatternError> {
+        let mut m = WildMatchPattern::try_compile(pattern, self.syntax.clone(), self.max_len)?;
+        m.set_case_insensitive(self.case_insensitive);
+        Ok(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PatternErrorKind, WildMatch};
+    use ntest::{assert_false, test_case};
+
+    #[test_case("*.rs")]
+    #[test_case("a[b]{c,d}\\")]
+    #[test_case("")]
+    fn default_like_new(pattern: &str) {
+        assert_eq!(
+            Ok(WildMatch::new(pattern)),
+            WildMatchBuilder::new().build(pattern)
+        );
+    }
+
+    #[test]
+    fn like_constructors() {
+        let builder = WildMatchBuilder::new();
+        assert_eq!(
+            Ok(WildMatch::new_case_insensitive("*.RS")),
+            builder.clone().case_insensitive(true).build("*.RS")
+        );
+        assert_eq!(
+            Ok(WildMatch::new_with_escape(r"#*a", '#')),
+            builder.clone().escape('#').build(r"#*a")
+        );
+        assert_eq!(
+            Ok(WildMatch::new_with_classes("[a-z]")),
+            builder.clone().classes(true).build("[a-z]")
+        );
+        assert_eq!(
+            Ok(WildMatch::new_with_braces("{a,b}")),
+            builder.clone().braces(true).build("{a,b}")
+        );
+        assert_eq!(
+            Ok(WildMatch::new_with_separators("src/**", &['/'])),
+            builder.separators(&['/']).build("src/**")
+        );
+    }
+
+    #[test]
+    fn combined_options() {
+        let m = WildMatchBuilder::new()
+            .case_insensitive(true)
+            .escape('\\')
+            .classes(true)
+            .braces(true)
+            .separators(&['/'])
+            .build(r"SRC/**/[a-c]\*.{RS,TOML}")
+            .unwrap();
+        assert!(m.matches("src/x/y/b*.toml"));
+        assert_false!(m.matches("src/x/y/bb.toml"));
+        assert_eq!(r"SRC/**/[a-c]\*.{RS,TOML}", m.pattern());
+        assert!(m.is_case_insensitive());
+        assert_eq!(Some('\\'), m.escape_char());
+        assert!(m.has_classes() && m.has_braces());
+        assert_eq!(&['/'], m.separators());
+    }
+
+    #[test_case("*", ".hidden", false)]
+    #[test_case("?hidden", ".hidden", false)]
+    #[test_case("[.]hidden", ".hidden", false)]
+    #[test_case(".*", ".hidden", true)]
+    #[test_case("*", "a.b", true)]
+    #[test_case("a*", "a.b", true)]
+    #[test_case("*/*", "a/.b", true)]
+    fn literal_leading_dot(pattern: &str, input: &str, expected: bool) {
+        let m = WildMatchBuilder::new()
+            .classes(true)
+            .literal_leading_dot(true)
+            .build(pattern)
+            .unwrap();
+        assert_eq!(expected, m.matches(input));
+        assert!(m.has_literal_leading_dot());
+    }
+
+    #[test_case("*/*", "a/.b", false)]
+    #[test_case("*/.*", "a/.b", true)]
+    #[test_case("**/*.rs", ".git/lib.rs", false)]
+    #[test_case("**/*.rs", "src/.git/lib.rs", false)]
+    #[test_case("**/*.rs", "src/a.b/lib.rs", true)]
+    #[test_case("src/**", "src/.git", false)]
+    #[test_case("src/**", "src/a/b", true)]
+    #[test_case("src/*", "src/.", false)]
+    fn literal_leading_dot_path(pattern: &str, input: &str, expected: bool) {
+        let m = WildMatchBuilder::new()
+            .separators(&['/'])
+            .literal_leading_dot(true)
+            .build(pattern)
+            .unwrap();
+        assert_eq!(expected, m.matches(input));
+        assert_eq!(expected, m.matches_bytes(input.as_bytes()));
+    }
+
+    #[test_case("cat", "dog_cat", true, false)]
+    #[test_case("cat", "cat_dog", false, true)]
+    #[test_case("cat", "a_cat_dog", false, false)]
+    #[test_case("c?t*", "a_cat", true, false)]
+    #[test_case("{a,b}c", "xbc", true, false)]
+    #[test_case("", "abc", true, true)]
+    fn anchoring(pattern: &str, input: &str, unanchored_start: bool, unanchored_end: bool) {
+        let builder = WildMatchBuilder::new().braces(true);
+        let start = builder.clone().anchor_start(false).build(pattern).unwrap();
+        let end = builder.clone().anchor_end(false).build(pattern).unwrap();
+        let both = builder.anchor_start(false).anchor_end(false);
+        assert_eq!(unanchored_start, start.matches(input));
+        assert_eq!(unanchored_end, end.matches(input));
+        assert!(both.build(pattern).unwrap().matches(input));
+        assert_eq!(pattern, both.build(pattern).unwrap().pattern());
+        assert_false!(start.is_anchored_start());
+        assert!(start.is_anchored_end());
+    }
+
+    #[test]
+    fn unanchored_path() {
+        let m = WildMatchBuilder::new()
+            .separators(&['/'])
+            .anchor_start(false)
+            .build("b/*.rs")
+            .unwrap();
+        assert!(m.matches("a/b/c.rs"));
+        assert_false!(m.matches("a/b/c/d.rs"));
+    }
+
+    #[test]
+    fn unanchored_captures() {
+        let m = WildMatchBuilder::new()
+            .anchor_start(false)
+            .anchor_end(false)
+            .build("c?t")
+            .unwrap();
+        let captures = m.captures("the cat sat").unwrap();
+        assert_eq!(1, captures.len());
+        assert_eq!(Some("a"), captures.get(0));
+    }
+
+    #[test_case("b", ".b", false)]
+    #[test_case("b", ".b", true)]
+    #[test_case("b", "a/.b", true)]
+    #[test_case("?b", ".xb", false)]
+    fn unanchored_start_skips_leading_dot(pattern: &str, input: &str, path: bool) {
+        let separators: &[char] = if path { &['/'] } else { &[] };
+        let m = WildMatchBuilder::new()
+            .literal_leading_dot(true)
+            .separators(separators)
+            .anchor_start(false)
+            .build(pattern)
+            .unwrap();
+        assert!(m.matches(input));
+        assert!(m.to_nfa().matches(input));
+        assert!(regex::Regex::new(&m.to_regex()).unwrap().is_match(input));
+        assert_false!(m.matches(".a"));
+    }
+
+    #[test]
+    fn unanchored_end_skips_leading_dot() {
+        let m = WildMatchBuilder::new()
+            .literal_leading_dot(true)
+            .separators(&['/'])
+            .anchor_end(false)
+            .build("a/")
+            .unwrap();
+        assert!(m.matches("a/.b/.c"));
+        assert!(m.to_nfa().matches("a/.b/.c"));
+        assert!(regex::Regex::new(&m.to_regex())
+            .unwrap()
+            .is_match("a/.b/.c"));
+        assert_false!(m.matches(".a/"));
+    }
+
+    #[test_case("[ab", "UnterminatedClass", 0)]
+    #[test_case("*.{rs", "UnterminatedAlternation", 2)]
+    #[test_case("a\\", "DanglingEscape", 1)]
+    #[test_case("abcdefghi", "TooLong { max_len: 8 }", 8)]
+    fn build_error(pattern: &str, kind: &str, offset: usize) {
+        let error = WildMatchBuilder::new()
+            .escape('\\')
+            .classes(true)
+            .braces(true)
+            .max_len(8)
+            .build(pattern)
+            .unwrap_err();
+        assert_eq!(kind, format!("{:?}", error.kind()));
+        assert_eq!(offset, error.offset());
+    }
+
+    #[test]
+    fn build_error_display() {
+        let builder = WildMatchBuilder::new().escape('\\').classes(true);
+        let error = builder.build("a*[b").unwrap_err();
+        assert_eq!(
+            "unterminated character class at offset 2",
+            error.to_string()
+        );
+        let error = builder.build("ab\\").unwrap_err();
+        assert_eq!("dangling escape character at offset 2", error.to_string());
+    }
+
+    #[test]
+    fn build_max_len_in_chars() {
+        let builder = WildMatchBuilder::new().max_len(4);
+        assert_eq!(Ok(WildMatch::new("äöü*")), builder.build("äöü*"));
+
+        let error = builder.build("äöü*?").unwrap_err();
+        assert_eq!(PatternErrorKind::TooLong { max_len: 4 }, error.kind());
+        assert_eq!(4, error.offset());
+        assert_eq!(
+            "pattern longer than 4 characters at offset 4",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "escape character cannot be a wildcard")]
+    fn escape_is_wildcard() {
+        WildMatchBuilder::new().escape('*');
+    }
+
+    #[test]
+    #[should_panic(expected = "path separator cannot be a wildcard")]
+    fn separator_is_wildcard() {
+        WildMatchBuilder::new().separators(&['?']);
+    }
+}
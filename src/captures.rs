@@ -0,0 +1,52 @@
+//! Substrings of an input matched by the wildcards of a pattern.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// The parts of an input matched by the wildcards of a pattern.
+///
+/// Created by [`WildMatchPattern::captures`](crate::WildMatchPattern::captures).
+/// Captures are indexed by the position of their wildcard in the pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Captures<'t> {
+    input: &'t str,
+    spans: Vec<Option<Range<usize>>>,
+}
+
+impl<'t> Captures<'t> {
+    pub(crate) fn new(input: &'t str, spans: Vec<Option<Range<usize>>>) -> Self {
+        Captures { input, spans }
+    }
+
+    /// Returns the substring matched by the wildcard with the given index, or
+    /// `None` if the wildcard is not part of the match.
+    pub fn get(&self, idx: usize) -> Option<&'t str> {
+        self.range(idx).map(|range| &self.input[range])
+    }
+
+    /// Returns the byte range in the input matched by the wildcard with the
+    /// given index, or `None` if the wildcard is not part of the match.
+    pub fn range(&self, idx: usize) -> Option<Range<usize>> {
+        self.spans.get(idx).cloned().flatten()
+    }
+
+    /// Returns the substrings matched by all wildcards in pattern order.
+    pub fn iter(&self) -> impl Iterator<Item = Option<&'t str>> + '_ {
+        (0..self.len()).map(move |idx| self.get(idx))
+    }
+
+    /// Returns the number of wildcards in the pattern.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns true if the pattern has no wildcards.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Returns the input the captures refer to.
+    pub fn input(&self) -> &'t str {
+        self.input
+    }
+}
@@ -0,0 +1,181 @@
+//! Bracket character classes such as `[abc]`, `[a-z]` and `[!x]`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str::Chars;
+
+/// A set of characters which matches a single input character.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub(crate) struct CharClass {
+    /// The class as written in the pattern, including the brackets.
+    source: String,
+    /// Inclusive character ranges. Single characters are stored as `(c, c)`.
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl CharClass {
+    /// Parses a class whose opening `[` has already been consumed.
+    ///
+    /// A `!` directly after the opening bracket negates the class. A `]` as
+    /// first member and a `-` as first or last member are taken literally.
+    /// Returns `None` without advancing `chars` if the class is unterminated.
+    pub(crate) fn parse(chars: &mut Chars<'_>, escape: Option<char>) -> Option<CharClass> {
+        let mut rest = chars.clone();
+        let mut ranges = Vec::new();
+
+        let mut negated = false;
+        if rest.as_str().starts_with('!') {
+            rest.next();
+            negated = true;
+        }
+
+        let mut first = true;
+        loop {
+            let mut c = rest.next()?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            if Some(c) == escape {
+                c = rest.next()?;
+            }
+
+            let mut lookahead = rest.clone();
+            if lookahead.next() == Some('-') {
+                match lookahead.next() {
+                    Some(']') | None => {}
+                    Some(mut end) => {
+                        if Some(end) == escape {
+                            end = lookahead.next()?;
+                        }
+                        rest = lookahead;
+                        ranges.push((c, end));
+                        continue;
+                    }
+                }
+            }
+            ranges.push((c, c));
+        }
+
+        let consumed = chars.as_str().len() - rest.as_str().len();
+        let mut source = String::with_capacity(consumed + 1);
+        source.push('[');
+        source.push_str(&chars.as_str()[..consumed]);
+        *chars = rest;
+        Some(CharClass {
+            source,
+            ranges,
+            negated,
+        })
+    }
+
+    /// Returns true if the class accepts the given character.
+    pub(crate) fn matches(&self, c: char, case_insensitive: bool) -> bool {
+        let contained = if case_insensitive {
+            self.contains(c)
+                || c.to_lowercase().any(|c| self.contains(c))
+                || c.to_uppercase().any(|c| self.contains(c))
+        } else {
+            self.contains(c)
+        };
+        contained != self.negated
+    }
+
+    fn contains(&self, c: char) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, end)| start <= c && c <= end)
+    }
+
+    /// Returns the class as written in the pattern.
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the inclusive character ranges of the class.
+    pub(crate) fn ranges(&self) -> &[(char, char)] {
+        &self.ranges
+    }
+
+    /// Returns true if the class matches all characters outside of its ranges.
+    pub(crate) fn is_negated(&self) -> bool {
+        self.negated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ntest::assert_false;
+    use ntest::test_case;
+
+    fn single(c: &str) -> char {
+        let mut chars = c.chars();
+        let c = chars.next().unwrap();
+        assert_eq!(None, chars.next());
+        c
+    }
+
+    fn parse(class: &str, escape: Option<char>) -> Option<CharClass> {
+        let mut chars = class.chars();
+        assert_eq!(Some('['), chars.next());
+        let parsed = CharClass::parse(&mut chars, escape);
+        if let Some(parsed) = &parsed {
+            assert_eq!(class, parsed.source);
+            assert_eq!("", chars.as_str());
+        }
+        parsed
+    }
+
+    #[test_case("[abc]", "b")]
+    #[test_case("[a-z]", "q")]
+    #[test_case("[a-cx-z]", "y")]
+    #[test_case("[!x]", "y")]
+    #[test_case("[]]", "]")]
+    #[test_case("[!]]", "a")]
+    #[test_case("[-a]", "-")]
+    #[test_case("[a-]", "-")]
+    #[test_case("[*?]", "?")]
+    #[test_case("[[]", "[")]
+    #[test_case("[ä-ü]", "ö")]
+    fn class_matches(class: &str, c: &str) {
+        assert!(parse(class, None).unwrap().matches(single(c), false));
+    }
+
+    #[test_case("[abc]", "d")]
+    #[test_case("[a-z]", "A")]
+    #[test_case("[!x]", "x")]
+    #[test_case("[!]]", "]")]
+    #[test_case("[-a]", "b")]
+    #[test_case("[z-a]", "q")]
+    fn class_no_match(class: &str, c: &str) {
+        assert_false!(parse(class, None).unwrap().matches(single(c), false));
+    }
+
+    #[test_case("[a-z]", "Q")]
+    #[test_case("[A-Z]", "q")]
+    #[test_case("[!a-z]", "1")]
+    #[test_case("[к]", "К")]
+    fn class_matches_case_insensitive(class: &str, c: &str) {
+        assert!(parse(class, None).unwrap().matches(single(c), true));
+    }
+
+    #[test_case(r"[\]]", "]")]
+    #[test_case(r"[\!]", "!")]
+    #[test_case(r"[a\-z]", "-")]
+    #[test_case(r"[\\]", "\\")]
+    #[test_case(r"[!-\]]", "[")]
+    fn class_matches_escaped(class: &str, c: &str) {
+        assert!(parse(class, Some('\\')).unwrap().matches(single(c), false));
+    }
+
+    #[test_case("[")]
+    #[test_case("[]")]
+    #[test_case("[!]")]
+    #[test_case("[abc")]
+    #[test_case("[a-")]
+    fn unterminated(class: &str) {
+        assert_eq!(None, parse(class, None));
+    }
+}
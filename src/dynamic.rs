@@ -0,0 +1,355 @@
+//! Patterns with wildcard characters chosen at runtime.
+
+use crate::engine::{Bytes, Matcher};
+use crate::parse::{Parser, Program, Syntax};
+use crate::translate;
+use crate::WildMatchPattern;
+use alloc::string::String;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::str::Chars;
+
+/// Wildcard and escape characters of a [`DynWildMatch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WildMatchOptions {
+    /// Character matching arbitrary many (including zero) characters.
+    pub multi_wildcard: char,
+    /// Character matching exactly one character.
+    pub single_wildcard: char,
+    /// Character which makes the following character match literally.
+    pub escape: Option<char>,
+    /// Whether characters are compared case-insensitively.
+    pub case_insensitive: bool,
+}
+
+impl Default for WildMatchOptions {
+    /// Options with `*` and `?` as wildcards like [`WildMatch`](crate::WildMatch).
+    fn default() -> Self {
+        WildMatchOptions {
+            multi_wildcard: '*',
+            single_wildcard: '?',
+            escape: None,
+            case_insensitive: false,
+        }
+    }
+}
+
+/// Wildcard matcher whose wildcard characters are chosen at runtime.
+///
+/// Behaves like a [`WildMatchPattern`] with the wildcards of the
+/// [`WildMatchOptions`] and can be converted from and into one:
+///
+/// ```
+/// # use wildmatch::{DynWildMatch, WildMatchOptions, WildMatchPattern};
+/// # use std::convert::TryFrom;
+/// let options = WildMatchOptions {
+///     multi_wildcard: '%',
+///     single_wildcard: '_',
+///     ..WildMatchOptions::default()
+/// };
+/// let m = DynWildMatch::new("%cat_", options);
+/// assert!(m.matches("dog_cats"));
+///
+/// let sql = WildMatchPattern::<'%', '_'>::try_from(m).unwrap();
+/// assert!(sql.matches("dog_cats"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DynWildMatch {
+    program: Program,
+    multi: char,
+    single: char,
+    case_insensitive: bool,
+}
+
+impl DynWildMatch {
+    /// Constructor with pattern and the characters to interpret it with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if both wildcards are identical or if the escape character is
+    /// one of the wildcards.
+    pub fn new(pattern: &str, options: WildMatchOptions) -> DynWildMatch {
+        assert!(
+            options.multi_wildcard != options.single_wildcard,
+            "single and multi wildcards cannot be the same"
+        );
+        assert!(
+            options.escape != Some(options.multi_wildcard)
+                && options.escape != Some(options.single_wildcard),
+            "escape character cannot be a wildcard"
+        );
+        let syntax = Syntax {
+            escape: options.escape,
+            ..Syntax::default()
+        };
+        let mut program = Parser::new(
+            pattern,
+            options.multi_wildcard,
+            options.single_wildcard,
+            &syntax,
+        )
+        .parse();
+        program.set_pattern(options.multi_wildcard, options.single_wildcard);
+        if options.case_insensitive {
+            program.fold_case();
+        }
+        DynWildMatch {
+            program,
+            multi: options.multi_wildcard,
+            single: options.single_wildcard,
+            case_insensitive: options.case_insensitive,
+        }
+    }
+
+    /// Returns true if pattern applies to the given input string
+    pub fn matches(&self, input: &str) -> bool {
+        self.matcher().matches(input)
+    }
+
+    /// Returns true if pattern applies to the given bytes, which do not have to
+    /// be valid UTF-8. See [`WildMatchPattern::matches_bytes`].
+    pub fn matches_bytes(&self, input: &[u8]) -> bool {
+        self.matcher().matches(&Bytes {
+            bytes: input,
+            utf8: false,
+        })
+    }
+
+    /// Returns true if pattern applies to the given bytes, which do not have to
+    /// be valid UTF-8. See [`WildMatchPattern::matches_bytes_utf8`].
+    pub fn matches_bytes_utf8(&self, input: &[u8]) -> bool {
+        self.matcher().matches(&Bytes {
+            bytes: input,
+            utf8: true,
+        })
+    }
+
+    /// Returns a regular expression which matches the same inputs as the
+    /// pattern. See [`WildMatchPattern::to_regex`].
+    pub fn to_regex(&self) -> String {
+        translate::to_regex(&self.program, self.program.syntax(), self.case_insensitive)
+    }
+
+    /// Returns the pattern string.
+    /// N.B. Consecutive multi-wildcards are simplified to a single multi-wildcard and
+    /// only characters which would otherwise have a special meaning stay escaped.
+    pub fn pattern(&self) -> &str {
+        self.program.pattern()
+    }
+
+    /// Returns an iterator over the chars of the pattern string.
+    pub fn pattern_chars(&self) -> Chars<'_> {
+        self.program.pattern().chars()
+    }
+
+    /// Returns the characters the pattern is interpreted with.
+    pub fn options(&self) -> WildMatchOptions {
+        WildMatchOptions {
+            multi_wildcard: self.multi,
+            single_wildcard: self.single,
+            escape: self.program.syntax().escape,
+            case_insensitive: self.case_insensitive,
+        }
+    }
+
+    fn matcher(&self) -> Matcher<'_> {
+        Matcher {
+            program: &self.program,
+            syntax: self.program.syntax(),
+            case_insensitive: self.case_insensitive,
+        }
+    }
+
+    /// Returns what identifies the pattern. The program is derived from it.
+    fn key(&self) -> (&str, char, char, bool, &Syntax) {
+        (
+            self.program.pattern(),
+            self.multi,
+            self.single,
+            self.case_insensitive,
+            self.program.syntax(),
+        )
+    }
+}
+
+impl Default for DynWildMatch {
+    /// Empty pattern with the default [`WildMatchOptions`], which only matches
+    /// the empty string.
+    fn default() -> Self {
+        DynWildMatch::new("", WildMatchOptions::default())
+    }
+}
+
+/// Patterns are equal if their simplified pattern strings and their options
+/// are equal. See [`WildMatchPattern`].
+impl PartialEq for DynWildMatch {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for DynWildMatch {}
+
+impl Hash for DynWildMatch {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl Ord for DynWildMatch {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl PartialOrd for DynWildMatch {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for DynWildMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.program.pattern())
+    }
+}
+
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char>
+    From<WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>> for DynWildMatch
+{
+    /// Keeps all syntax of the pattern, including syntax which cannot be
+    /// chosen with [`WildMatchOptions`].
+    fn from(m: WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>) -> Self {
+        DynWildMatch {
+            program: m.program,
+            multi: MULTI_WILDCARD,
+            single: SINGLE_WILDCARD,
+            case_insensitive: m.case_insensitive,
+        }
+    }
+}
+
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> TryFrom<DynWildMatch>
+    for WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+    type Error = DynWildMatch;
+
+    /// Fails with the given pattern if its wildcards differ from the wildcards
+    /// of the target type.
+    fn try_from(m: DynWildMatch) -> Result<Self, DynWildMatch> {
+        if m.multi != MULTI_WILDCARD || m.single != SINGLE_WILDCARD {
+            return Err(m);
+        }
+        Ok(WildMatchPattern {
+            program: m.program,
+            case_insensitive: m.case_insensitive,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WildMatch;
+    use ntest::{assert_false, test_case};
+
+    fn options(multi: &str, single: &str) -> WildMatchOptions {
+        WildMatchOptions {
+            multi_wildcard: multi.chars().next().unwrap(),
+            single_wildcard: single.chars().next().unwrap(),
+            ..WildMatchOptions::default()
+        }
+    }
+
+    #[test]
+    fn default_is_empty_pattern() {
+        let m = DynWildMatch::default();
+        assert_eq!(DynWildMatch::new("", WildMatchOptions::default()), m);
+        assert_eq!(WildMatchOptions::default(), m.options());
+        assert!(m.matches(""));
+        assert_false!(m.matches("*"));
+        assert_eq!(Ok(WildMatch::new("")), WildMatch::try_from(m));
+    }
+
+    #[test_case("%", "_", "%cat_", "dog_cats", true)]
+    #[test_case("%", "_", "%cat_", "dog_cat", false)]
+    #[test_case("%", "_", "*cat?", "*cat?", true)]
+    #[test_case("*", "?", "*cat?", "dog_cats", true)]
+    #[test_case("#", "%", "#%%", "ab", true)]
+    fn matches_with_options(multi: &str, single: &str, pattern: &str, input: &str, expected: bool) {
+        let m = DynWildMatch::new(pattern, options(multi, single));
+        assert_eq!(expected, m.matches(input));
+        assert_eq!(expected, m.matches_bytes_utf8(input.as_bytes()));
+    }
+
+    #[test]
+    fn matches_like_const_generic_pattern() {
+        let patterns = ["", "%", "a%%b", "_a_", "%.rs", "a%b%c"];
+        let inputs = ["", "a", "ab", "bab", "lib.rs", "axbyc", "abc"];
+        for pattern in patterns.iter() {
+            let dynamic = DynWildMatch::new(pattern, options("%", "_"));
+            let generic = WildMatchPattern::<'%', '_'>::new(pattern);
+            assert_eq!(generic.pattern(), dynamic.pattern());
+            for input in inputs.iter() {
+                assert_eq!(generic.matches(input), dynamic.matches(input));
+            }
+        }
+    }
+
+    #[test]
+    fn escape_and_case_insensitive() {
+        let m = DynWildMatch::new(
+            r"100\%%",
+            WildMatchOptions {
+                escape: Some('\\'),
+                case_insensitive: true,
+                ..options("%", "_")
+            },
+        );
+        assert!(m.matches("100% DONE"));
+        assert!(m.matches("100%done"));
+        assert_false!(m.matches("1000"));
+        assert_eq!(r"100\%%", m.to_string());
+    }
+
+    #[test]
+    fn convert_from_and_into_const_generic() {
+        let m = DynWildMatch::from(WildMatch::new_case_insensitive("*.RS"));
+        assert_eq!(options("*", "?").multi_wildcard, m.options().multi_wildcard);
+        assert!(m.options().case_insensitive);
+        assert!(m.matches("lib.rs"));
+
+        let back = WildMatch::try_from(m.clone()).unwrap();
+        assert_eq!(WildMatch::new_case_insensitive("*.RS"), back);
+
+        let err = WildMatchPattern::<'%', '_'>::try_from(m.clone()).unwrap_err();
+        assert_eq!(m, err);
+    }
+
+    #[test]
+    fn convert_keeps_syntax() {
+        let m = DynWildMatch::from(WildMatch::new_with_braces("*.{rs,toml}"));
+        assert!(m.matches("Cargo.toml"));
+        assert_eq!("*.{rs,toml}", m.pattern());
+    }
+
+    #[test]
+    #[should_panic(expected = "single and multi wildcards cannot be the same")]
+    fn same_wildcards() {
+        DynWildMatch::new("", options("%", "%"));
+    }
+
+    #[test]
+    #[should_panic(expected = "escape character cannot be a wildcard")]
+    fn escape_is_wildcard() {
+        DynWildMatch::new(
+            "",
+            WildMatchOptions {
+                escape: Some('_'),
+                ..options("%", "_")
+            },
+        );
+    }
+}
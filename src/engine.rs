@@ -0,0 +1,638 @@
+//! Matching of compiled patterns against input strings.
+
+use crate::parse::{Program, Shape, Syntax, Token};
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Input which can be matched against a pattern. Positions in the input are
+/// byte offsets.
+pub(crate) trait Input {
+    /// Returns the length of the input in bytes.
+    fn len(&self) -> usize;
+
+    /// Returns the char starting at `offset` and the offset after it. Returns
+    /// `None` at the end of the input or if there is no valid char at `offset`.
+    fn char_at(&self, offset: usize) -> Option<(char, usize)>;
+
+    /// Returns the offset after the unit consumed by a single-character
+    /// wildcard at `offset`, or `None` at the end of the input.
+    fn skip(&self, offset: usize) -> Option<usize>;
+
+    /// Returns the offset after `literal` if the input continues with it at
+    /// `offset`.
+    fn literal_at(&self, offset: usize, literal: &str) -> Option<usize>;
+
+    /// Returns the offset of the first occurrence of `literal` in the input
+    /// at or after `offset`.
+    fn find_literal(&self, offset: usize, literal: &str) -> Option<usize>;
+
+    /// Returns the input as bytes.
+    fn as_bytes(&self) -> &[u8];
+
+    /// Returns the input as str if it is one.
+    fn as_str(&self) -> Option<&str>;
+}
+
+impl Input for str {
+    fn len(&self) -> usize {
+        str::len(self)
+    }
+
+    fn char_at(&self, offset: usize) -> Option<(char, usize)> {
+        match self.as_bytes().get(offset) {
+            Some(&byte) if byte.is_ascii() => Some((byte as char, offset + 1)),
+            _ => self
+                .get(offset..)?
+                .chars()
+                .next()
+                .map(|c| (c, offset + c.len_utf8())),
+        }
+    }
+
+    fn skip(&self, offset: usize) -> Option<usize> {
+        // A str is valid UTF-8, so the length of a char follows from its
+        // first byte without decoding it.
+        self.as_bytes()
+            .get(offset)
+            .map(|&byte| offset + utf8_len(byte))
+    }
+
+    fn literal_at(&self, offset: usize, literal: &str) -> Option<usize> {
+        Bytes {
+            bytes: self.as_bytes(),
+            utf8: true,
+        }
+        .literal_at(offset, literal)
+    }
+
+    fn find_literal(&self, offset: usize, literal: &str) -> Option<usize> {
+        // Offsets into a str are always at char boundaries. Uses the two-way
+        // substring search of the standard library.
+        self[offset..].find(literal).map(|pos| offset + pos)
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        str::as_bytes(self)
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+/// Bytes which are not necessarily valid UTF-8.
+pub(crate) struct Bytes<'a> {
+    pub(crate) bytes: &'a [u8],
+    /// Whether a single-character wildcard consumes a whole UTF-8 encoded
+    /// char instead of a single byte where the input is valid UTF-8.
+    pub(crate) utf8: bool,
+}
+
+impl Input for Bytes<'_> {
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn char_at(&self, offset: usize) -> Option<(char, usize)> {
+        match self.bytes.get(offset) {
+            Some(&byte) if byte.is_ascii() => return Some((byte as char, offset + 1)),
+            None => return None,
+            _ => {}
+        }
+        let bytes = &self.bytes[offset..self.bytes.len().min(offset + 4)];
+        let valid = match core::str::from_utf8(bytes) {
+            Ok(valid) => valid,
+            Err(err) => core::str::from_utf8(&bytes[..err.valid_up_to()]).ok()?,
+        };
+        valid.chars().next().map(|c| (c, offset + c.len_utf8()))
+    }
+
+    fn skip(&self, offset: usize) -> Option<usize> {
+        if offset >= self.bytes.len() {
+            None
+        } else if self.utf8 && !self.bytes[offset].is_ascii() {
+            self.char_at(offset)
+                .map_or(Some(offset + 1), |(_, end)| Some(end))
+        } else {
+            Some(offset + 1)
+        }
+    }
+
+    fn literal_at(&self, offset: usize, literal: &str) -> Option<usize> {
+        // Literals are compared without decoding the input, since a valid
+        // UTF-8 literal can only equal a sequence of whole chars.
+        self.bytes[offset..]
+            .starts_with(literal.as_bytes())
+            .then(|| offset + literal.len())
+    }
+
+    fn find_literal(&self, offset: usize, literal: &str) -> Option<usize> {
+        // Scans for the first byte of the literal and only compares the rest
+        // where it occurs. Like in `literal_at` no decoding is needed, since
+        // a valid UTF-8 literal never starts within an encoded char.
+        let (&first, rest) = match literal.as_bytes().split_first() {
+            Some(split) => split,
+            None => return Some(offset),
+        };
+        let mut start = offset;
+        while let Some(pos) = self.bytes[start..].iter().position(|&byte| byte == first) {
+            let pos = start + pos;
+            if self.bytes[pos + 1..].starts_with(rest) {
+                return Some(pos);
+            }
+            start = pos + 1;
+        }
+        None
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.bytes
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Returns the length of a UTF-8 encoded char in bytes from its first byte.
+fn utf8_len(first: u8) -> usize {
+    match first {
+        0x00..=0x7F => 1,
+        0x80..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xFF => 4,
+    }
+}
+
+/// Matches the tokens of a compiled pattern against input strings.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Matcher<'p> {
+    pub(crate) program: &'p Program,
+    pub(crate) syntax: &'p Syntax,
+    pub(crate) case_insensitive: bool,
+}
+
+impl<'p> Matcher<'p> {
+    /// Returns true if the pattern matches the entire input.
+    pub(crate) fn matches<I: Input + ?Sized>(&self, input: &I) -> bool {
+        if !self.program.is_linear() {
+            return Backtrack::new(*self, input, true).matches_at(0, 0);
+        }
+        if !self.case_insensitive {
+            let bytes = input.as_bytes();
+            let literal = self.program.shape_literal();
+            match self.program.shape {
+                Shape::Exact => return bytes == literal.as_bytes(),
+                Shape::Prefix => return bytes.starts_with(literal.as_bytes()),
+                Shape::Suffix => return bytes.ends_with(literal.as_bytes()),
+                Shape::Contains => return input.find_literal(0, literal).is_some(),
+                Shape::Tokens => {}
+            }
+        }
+        if let (Some(shift_and), Some(input)) = (&self.program.shift_and, input.as_str()) {
+            if let Some(matched) = shift_and.matches(input, self.case_insensitive) {
+                return matched;
+            }
+        }
+        let tokens = &self.program.tokens;
+        if tokens.is_empty() {
+            return input.len() == 0;
+        }
+        // Whether the input after a multi-wildcard can be searched for the
+        // next literal instead of trying every end of the multi-wildcard.
+        let searchable = !self.case_insensitive
+            && self.syntax.separators.is_empty()
+            && !self.syntax.literal_leading_dot;
+
+        let mut pattern_idx = 0;
+        let mut offset = 0;
+        // Index of the last multi-wildcard and the end of the input it consumes.
+        let mut star: Option<(usize, usize)> = None;
+
+        loop {
+            if pattern_idx < tokens.len() && tokens[pattern_idx] == Token::Multi {
+                if let (true, Some(&Token::Literal(start, end))) =
+                    (searchable, tokens.get(pattern_idx + 1))
+                {
+                    // Skip the input up to where the following literal occurs.
+                    match self.find_literal(input, offset, start, end, pattern_idx + 2) {
+                        Some(pos) => offset = pos,
+                        None => return false,
+                    }
+                }
+                star = Some((pattern_idx, offset));
+                pattern_idx += 1;
+            } else if offset == input.len() {
+                break;
+            } else if let Some(next) = tokens
+                .get(pattern_idx)
+                .and_then(|_| self.step(pattern_idx, input, offset))
+            {
+                pattern_idx += 1;
+                offset = next;
+            } else if let Some((star_idx, star_end)) = star {
+                // In path mode a multi-wildcard cannot be extended across a
+                // separator, and never across a dot which has to match literally.
+                if self.is_separator_at(input, star_end) || self.is_hidden_at(input, star_end) {
+                    return false;
+                }
+                // The multi-wildcard ends before the current offset, which is
+                // not at the end of the input. Continue with the multi-wildcard
+                // to search for the following literal again.
+                pattern_idx = star_idx;
+                offset = input.skip(star_end).unwrap_or(offset);
+            } else {
+                return false;
+            }
+        }
+
+        while pattern_idx < tokens.len() && tokens[pattern_idx] == Token::Multi {
+            pattern_idx += 1;
+        }
+
+        // If we have reached the end of both the pattern and the text, the pattern matches the text.
+        pattern_idx == tokens.len()
+    }
+
+    /// Returns the offset of the first occurrence of the literal in the input
+    /// at or after `offset`. The last token of the pattern can only occur at
+    /// the end of the input.
+    fn find_literal<I: Input + ?Sized>(
+        &self,
+        input: &I,
+        offset: usize,
+        start: u32,
+        end: u32,
+        next_idx: usize,
+    ) -> Option<usize> {
+        let literal = self.program.literal(start, end);
+        if next_idx == self.program.tokens.len() {
+            input
+                .len()
+                .checked_sub(literal.len())
+                .filter(|&pos| pos >= offset && input.as_bytes().ends_with(literal.as_bytes()))
+        } else {
+            input.find_literal(offset, literal)
+        }
+    }
+
+    /// Returns the offset after the input consumed by the literal or
+    /// single-character token with index `idx` at `offset`, or `None` if the
+    /// token does not match there.
+    fn step<I: Input + ?Sized>(&self, idx: usize, input: &I, offset: usize) -> Option<usize> {
+        let token = self.program.tokens[idx];
+        match token {
+            Token::Literal(start, end) if !self.case_insensitive => {
+                input.literal_at(offset, self.program.literal(start, end))
+            }
+            Token::Literal(..) => {
+                let (start, end) = self.program.folded()[idx];
+                literal_at_ignore_case(input, offset, self.program.literal(start, end))
+            }
+            Token::Single | Token::Class(_) if self.is_hidden_at(input, offset) => None,
+            Token::Single if !self.is_separator_at(input, offset) => input.skip(offset),
+            Token::Single => None,
+            _ => input
+                .char_at(offset)
+                .filter(|&(c, _)| self.token_matches(token, c))
+                .map(|(_, end)| end),
+        }
+    }
+
+    fn is_separator_at<I: Input + ?Sized>(&self, input: &I, offset: usize) -> bool {
+        !self.syntax.separators.is_empty()
+            && input
+                .char_at(offset)
+                .is_some_and(|(c, _)| self.syntax.is_separator(c))
+    }
+
+    /// Returns true if the input has a `.` at `offset` which can only be matched
+    /// literally since it starts the input or a path segment.
+    fn is_hidden_at<I: Input + ?Sized>(&self, input: &I, offset: usize) -> bool {
+        self.syntax.literal_leading_dot
+            && input.literal_at(offset, ".").is_some()
+            && (offset == 0 || self.ends_with_separator(input, 0, offset))
+    }
+
+    /// Returns the next possible end offset after `end` of a multi-char token
+    /// matching the input from `offset` on. The first possible end is `offset`.
+    fn next_multi_end<I: Input + ?Sized>(
+        &self,
+        token: Token,
+        input: &I,
+        offset: usize,
+        mut end: usize,
+    ) -> Option<usize> {
+        loop {
+            end = match token {
+                Token::Skip => input.skip(end)?,
+                // Multi-wildcards do not extend across a separator.
+                Token::Multi if self.is_separator_at(input, end) => return None,
+                _ if self.is_hidden_at(input, end) => return None,
+                _ => input.skip(end)?,
+            };
+            // Path components always end right after a separator.
+            if !matches!(token, Token::Components(_))
+                || self.ends_with_separator(input, offset, end)
+            {
+                return Some(end);
+            }
+        }
+    }
+
+    /// Returns true if the unit before `end` is a separator.
+    fn ends_with_separator<I: Input + ?Sized>(&self, input: &I, start: usize, end: usize) -> bool {
+        // Separators are at most four bytes long.
+        (start.max(end.saturating_sub(4))..end).any(|offset| {
+            input
+                .char_at(offset)
+                .is_some_and(|(c, next)| next == end && self.syntax.is_separator(c))
+        })
+    }
+
+    /// Returns true if a single-character token accepts the given input char.
+    fn token_matches(&self, token: Token, input_char: char) -> bool {
+        match token {
+            Token::Single => !self.syntax.is_separator(input_char),
+            Token::Separator(_) => self.syntax.is_separator(input_char),
+            Token::Class(idx) => {
+                !self.syntax.is_separator(input_char)
+                    && self
+                        .program
+                        .class(idx)
+                        .matches(input_char, self.case_insensitive)
+            }
+            Token::Literal(..)
+            | Token::Multi
+            | Token::GlobStar
+            | Token::Skip
+            | Token::Components(_)
+            | Token::Alt(_)
+            | Token::Jump(_) => false,
+        }
+    }
+}
+
+/// Returns the offset after the input which equals the `folded` text when
+/// folded itself, if any. Each input char is folded without allocating and
+/// compared with one char of the folded text.
+fn literal_at_ignore_case<I: Input + ?Sized>(
+    input: &I,
+    mut offset: usize,
+    folded: &str,
+) -> Option<usize> {
+    for expected in folded.chars() {
+        let (c, next) = input.char_at(offset)?;
+        if fold(c) != expected {
+            return None;
+        }
+        offset = next;
+    }
+    Some(offset)
+}
+
+/// Returns true if `haystack` contains `needle` when ignoring ASCII case. The
+/// needle has to be in ASCII lowercase.
+pub(crate) fn contains_ignore_ascii_case(haystack: &[u8], needle: &[u8]) -> bool {
+    if needle.is_empty() {
+        return true;
+    }
+    if haystack.len() < needle.len() {
+        return false;
+    }
+    // Scans for both cases of one byte at once with vector instructions and
+    // only compares the whole needle where one of them occurs. A letter is
+    // picked over spaces and punctuation, which tend to be far more common.
+    let at = needle.iter().position(u8::is_ascii_alphabetic).unwrap_or(0);
+    let byte = needle[at];
+    let end = haystack.len() - needle.len() + at + 1;
+    memchr::memchr2_iter(byte, byte.to_ascii_uppercase(), &haystack[at..end])
+        .any(|pos| haystack[pos..pos + needle.len()].eq_ignore_ascii_case(needle))
+}
+
+/// Returns the char which `c` is compared as when ignoring case: its lowercase
+/// if that is a single char and `c` itself otherwise. Chars which are lowercase
+/// as several chars, like `İ`, thus only equal themselves.
+pub(crate) fn fold(c: char) -> char {
+    if c.is_ascii() {
+        return c.to_ascii_lowercase();
+    }
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => c,
+    }
+}
+
+/// Backtracking search for a match of the tokens in an input.
+///
+/// Used for patterns with alternations or `**` path segments, which cannot be
+/// matched by only backtracking to the last multi-wildcard, and to locate and
+/// capture matches. Multi-char tokens try the shortest range first and
+/// alternations try their branches in order.
+pub(crate) struct Backtrack<'p, 'i, I: Input + ?Sized> {
+    matcher: Matcher<'p>,
+    input: &'i I,
+    /// Whether a match has to end at the end of the input.
+    anchored: bool,
+    /// Token index and input offset pairs of choices which are known not to
+    /// match, with the last offset the choice tried. A multi-char token which
+    /// failed at an offset also fails at the later offsets it tried, since it
+    /// can only end where it could end from the earlier offset. Every token is
+    /// thus tried at most once per input offset.
+    failed: BTreeMap<(usize, usize), usize>,
+    /// Tokens of the current attempt which can match in other ways. The stack
+    /// replaces recursion, so that long patterns cannot overflow the stack.
+    choices: Vec<Choice>,
+    /// Whether the input ranges consumed by the tokens are recorded.
+    record: bool,
+    /// Token indices and input ranges consumed by the tokens of the current
+    /// attempt in the order they were matched.
+    spans: Vec<(usize, Range<usize>)>,
+    /// End offset of the last match.
+    end: usize,
+}
+
+/// An alternation or multi-char token whose ways of matching the input are
+/// tried one after the other.
+struct Choice {
+    idx: usize,
+    offset: usize,
+    /// Index of the next branch of an alternation or next end offset of a
+    /// multi-char token to try. `None` once all have been tried.
+    next: Option<usize>,
+    /// The last end offset of a multi-char token which was tried.
+    last: usize,
+    /// Number of spans recorded before the choice.
+    spans: usize,
+}
+
+impl<'p, 'i, I: Input + ?Sized> Backtrack<'p, 'i, I> {
+    pub(crate) fn new(matcher: Matcher<'p>, input: &'i I, anchored: bool) -> Self {
+        Backtrack {
+            matcher,
+            input,
+            anchored,
+            failed: BTreeMap::new(),
+            choices: Vec::new(),
+            record: false,
+            spans: Vec::new(),
+            end: 0,
+        }
+    }
+
+    /// Records the input range consumed by each token of the next match.
+    pub(crate) fn record_spans(&mut self) {
+        self.record = true;
+    }
+
+    /// Returns the input range consumed by each token of the last match.
+    pub(crate) fn into_spans(self) -> Vec<Option<Range<usize>>> {
+        let mut spans = vec![None; self.matcher.program.tokens.len()];
+        for (idx, span) in self.spans {
+            spans[idx] = Some(span);
+        }
+        spans
+    }
+
+    /// Returns the end offset of the last match.
+    pub(crate) fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns true if the tokens from `idx` on match the input from `offset` on.
+    pub(crate) fn matches_at(&mut self, idx: usize, offset: usize) -> bool {
+        self.spans.clear();
+        let matched = self.matches_from(idx, offset);
+        self.choices.clear();
+        if !matched {
+            // Tokens of a failed attempt are not part of the match.
+            self.spans.clear();
+        }
+        matched
+    }
+
+    fn matches_from(&mut self, mut idx: usize, mut offset: usize) -> bool {
+        let matcher = self.matcher;
+        let input = self.input;
+        loop {
+            let next = match matcher.program.tokens.get(idx) {
+                None if self.anchored && offset != input.len() => None,
+                None => {
+                    self.end = offset;
+                    return true;
+                }
+                Some(&Token::Jump(target)) => Some((target as usize, offset)),
+                Some(
+                    &token @ (Token::Alt(_)
+                    | Token::Multi
+                    | Token::GlobStar
+                    | Token::Skip
+                    | Token::Components(_)),
+                ) => {
+                    if self.has_failed(token, idx, offset) {
+                        None
+                    } else {
+                        // Alternations start with their first branch and
+                        // multi-char tokens with the empty range.
+                        let next = if matches!(token, Token::Alt(_)) {
+                            0
+                        } else {
+                            offset
+                        };
+                        self.choices.push(Choice {
+                            idx,
+                            offset,
+                            next: Some(next),
+                            last: offset,
+                            spans: self.spans.len(),
+                        });
+                        self.retry()
+                    }
+                }
+                Some(_) => matcher.step(idx, input, offset).map(|end| {
+                    self.record(idx, offset..end);
+                    (idx + 1, end)
+                }),
+            };
+            match next.or_else(|| self.retry()) {
+                Some((next_idx, next_offset)) => {
+                    idx = next_idx;
+                    offset = next_offset;
+                }
+                None => return false,
+            }
+        }
+    }
+
+    /// Returns the token index and input offset at which matching continues
+    /// with the next untried way of matching of the innermost choice. Choices
+    /// which have been tried in all ways are known to fail and are dropped.
+    fn retry(&mut self) -> Option<(usize, usize)> {
+        let matcher = self.matcher;
+        while let Some(choice) = self.choices.last_mut() {
+            let (idx, offset) = (choice.idx, choice.offset);
+            let next = match choice.next {
+                None => {
+                    self.failed.insert((idx, offset), choice.last);
+                    self.choices.pop();
+                    continue;
+                }
+                Some(next) => next,
+            };
+            let token = matcher.program.tokens[idx];
+            if !matches!(token, Token::Alt(_)) {
+                choice.last = next;
+            }
+            choice.next = match token {
+                Token::Alt(alt) => Some(next + 1)
+                    .filter(|&branch| branch < matcher.program.alternation(alt).branches.len()),
+                _ => matcher.next_multi_end(token, self.input, offset, next),
+            };
+            // Later tokens of the previous attempt are not part of the match.
+            let spans = choice.spans;
+            self.spans.truncate(spans);
+            return match token {
+                Token::Alt(alt) => Some((matcher.program.alternation(alt).branches[next], offset)),
+                _ => {
+                    self.record(idx, offset..next);
+                    Some((idx + 1, next))
+                }
+            };
+        }
+        None
+    }
+
+    /// Returns true if the choice of `token` with index `idx` at `offset` is
+    /// known not to match.
+    fn has_failed(&self, token: Token, idx: usize, offset: usize) -> bool {
+        let (start, last) = match self.failed.range((idx, 0)..=(idx, offset)).next_back() {
+            Some((&(_, start), &last)) => (start, last),
+            None => return false,
+        };
+        // The ranges tried from different offsets either are disjoint or end
+        // at the same offset, so the closest earlier one is enough to check.
+        if start == offset {
+            return true;
+        }
+        if offset > last {
+            return false;
+        }
+        match token {
+            Token::Multi | Token::GlobStar | Token::Skip => true,
+            // Path components only end after separators, so a later offset
+            // was tried as an end only if it follows a separator as well.
+            Token::Components(_) => self.matcher.ends_with_separator(self.input, start, offset),
+            _ => false,
+        }
+    }
+
+    /// Records the input range consumed by the token with index `idx`.
+    fn record(&mut self, idx: usize, span: Range<usize>) {
+        if self.record {
+            self.spans.push((idx, span));
+        }
+    }
+}
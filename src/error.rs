@@ -0,0 +1,104 @@
+//! Errors of invalid patterns and failed translations.
+
+use core::fmt;
+
+/// Error returned when a pattern cannot be parsed.
+///
+/// Returned for too long patterns by
+/// [`WildMatchPattern::try_new_with_max_len`](crate::WildMatchPattern::try_new_with_max_len)
+/// and for invalid syntax by [`WildMatchBuilder::build`](crate::WildMatchBuilder::build)
+/// and [`WildMatchPattern::from_sql_like`](crate::WildMatchPattern::from_sql_like).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    kind: PatternErrorKind,
+    offset: usize,
+}
+
+/// The reason why a pattern cannot be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PatternErrorKind {
+    /// A `[` character class is not closed by a `]`.
+    UnterminatedClass,
+    /// A `{` alternation is not closed by a `}`.
+    UnterminatedAlternation,
+    /// The escape character is the last character of the pattern.
+    DanglingEscape,
+    /// A `{` alternation is nested in more alternations than allowed.
+    NestingTooDeep {
+        /// Maximum number of alternations which can be nested in each other.
+        max_depth: usize,
+    },
+    /// The pattern has more characters than allowed.
+    TooLong {
+        /// Maximum number of characters of the pattern.
+        max_len: usize,
+    },
+}
+
+impl PatternError {
+    pub(crate) fn new(kind: PatternErrorKind, offset: usize) -> Self {
+        PatternError { kind, offset }
+    }
+
+    /// Returns the reason why the pattern cannot be parsed.
+    pub fn kind(&self) -> PatternErrorKind {
+        self.kind
+    }
+
+    /// Returns the offset in chars of the pattern at which the error occurs.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            PatternErrorKind::UnterminatedClass => write!(f, "unterminated character class")?,
+            PatternErrorKind::UnterminatedAlternation => write!(f, "unterminated alternation")?,
+            PatternErrorKind::DanglingEscape => write!(f, "dangling escape character")?,
+            PatternErrorKind::NestingTooDeep { max_depth } => {
+                write!(f, "alternations nested deeper than {} levels", max_depth)?
+            }
+            PatternErrorKind::TooLong { max_len } => {
+                write!(f, "pattern longer than {} characters", max_len)?
+            }
+        }
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PatternError {}
+
+/// Error returned when a pattern cannot be expressed in another pattern syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TranslateError {
+    /// The pattern uses syntax which has no equivalent in the target syntax,
+    /// like character classes or path separators.
+    UnsupportedSyntax,
+    /// The pattern matches a wildcard character of the target syntax
+    /// literally, but has no escape character to express that.
+    EscapeRequired {
+        /// The literal character which is a wildcard in the target syntax.
+        literal: char,
+    },
+}
+
+impl fmt::Display for TranslateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranslateError::UnsupportedSyntax => {
+                write!(f, "pattern syntax is not supported by the target syntax")
+            }
+            TranslateError::EscapeRequired { literal } => {
+                write!(f, "literal {:?} requires an escape character", literal)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TranslateError {}
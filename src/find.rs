@@ -0,0 +1,69 @@
+//! Searching for matches of a pattern within a larger string.
+
+use crate::engine::Backtrack;
+use core::ops::Range;
+
+/// An iterator over the byte ranges of all non-overlapping matches of a
+/// pattern within a haystack.
+///
+/// Created by [`WildMatchPattern::find_iter`](crate::WildMatchPattern::find_iter).
+pub struct FindIter<'p, 't> {
+    backtrack: Backtrack<'p, 't, str>,
+    haystack: &'t str,
+    /// Literal every match starts with, so that only its occurrences in the
+    /// haystack have to be tried as starts.
+    prefix: &'p str,
+    /// Offset from which on the next match is searched. Past the end of the
+    /// haystack once all matches have been found.
+    start: usize,
+}
+
+impl<'p, 't> FindIter<'p, 't> {
+    pub(crate) fn new(
+        backtrack: Backtrack<'p, 't, str>,
+        haystack: &'t str,
+        prefix: &'p str,
+    ) -> Self {
+        FindIter {
+            backtrack,
+            haystack,
+            prefix,
+            start: 0,
+        }
+    }
+
+    /// Returns the offset after the char starting at `offset`.
+    fn next_char(&self, offset: usize) -> usize {
+        self.haystack[offset..]
+            .chars()
+            .next()
+            .map_or(offset + 1, |c| offset + c.len_utf8())
+    }
+}
+
+impl<'p, 't> Iterator for FindIter<'p, 't> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        while self.start <= self.haystack.len() {
+            let start = match self.haystack[self.start..].find(self.prefix) {
+                Some(pos) => self.start + pos,
+                None => break,
+            };
+            // Failures of earlier starts are remembered by the backtracker, so
+            // the parts of the haystack they tried are not searched again.
+            if self.backtrack.matches_at(0, start) {
+                let end = self.backtrack.end();
+                // Continue after an empty match at the next char.
+                self.start = if end > start {
+                    end
+                } else {
+                    self.next_char(start)
+                };
+                return Some(start..end);
+            }
+            self.start = self.next_char(start);
+        }
+        None
+    }
+}
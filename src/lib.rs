@@ -3,8 +3,6 @@
 //!
 //! See also the example described on [wikipedia](https://en.wikipedia.org/wiki/Matching_wildcards) for matching wildcards.
 //!
-//! No escape characters are defined.
-//!
 //! - `?` matches exactly one occurrence of any character.
 //! - `*` matches arbitrary many (including zero) occurrences of any character.
 //!
@@ -32,9 +30,114 @@
 //! # extern crate wildmatch; use wildmatch::WildMatchPattern;
 //! assert!(WildMatchPattern::<'%', '_'>::new("%cat%").matches("dog_cat_dog"));
 //! ```
+//! Wildcard characters which are only known at runtime can be chosen with
+//! [`WildMatchOptions`] for a [`DynWildMatch`].
+//!
+//! By default no escape characters are defined. Patterns created with
+//! [`WildMatchPattern::new_escaped`] treat `\` as an escape character which
+//! makes the following character match literally. A different escape character
+//! can be chosen with [`WildMatchPattern::new_with_escape`]:
+//! ``` rust
+//! # extern crate wildmatch; use wildmatch::WildMatch;
+//! assert!(WildMatch::new_escaped(r"what\?.txt").matches("what?.txt"));
+//! assert!(!WildMatch::new_escaped(r"what\?.txt").matches("whats.txt"));
+//! assert!(WildMatch::new_with_escape("5#*#?", '#').matches("5*?"));
+//! ```
+//!
+//! Bracket character classes like `[abc]`, `[a-z]` or `[!a-z]` can be enabled
+//! with [`WildMatchPattern::new_with_classes`]:
+//! ``` rust
+//! # extern crate wildmatch; use wildmatch::WildMatch;
+//! assert!(WildMatch::new_with_classes("log[0-9].txt").matches("log1.txt"));
+//! assert!(!WildMatch::new_with_classes("log[!0-9].txt").matches("log1.txt"));
+//! ```
+//!
+//! Brace alternations like `{jpg,png}` can be enabled with
+//! [`WildMatchPattern::new_with_braces`]:
+//! ``` rust
+//! # extern crate wildmatch; use wildmatch::WildMatch;
+//! assert!(WildMatch::new_with_braces("*.{jpg,png}").matches("cat.png"));
+//! assert!(!WildMatch::new_with_braces("*.{jpg,png}").matches("cat.gif"));
+//! ```
+//!
+//! In path mode, enabled with [`WildMatchPattern::new_with_separators`], the
+//! wildcards do not match path separators and `**` matches whole path components:
+//! ``` rust
+//! # extern crate wildmatch; use wildmatch::WildMatch;
+//! assert!(WildMatch::new_with_separators("src/**/*.rs", &['/']).matches("src/a/lib.rs"));
+//! assert!(!WildMatch::new_with_separators("src/*.rs", &['/']).matches("src/a/lib.rs"));
+//! ```
+//!
+//! Options can be combined with a [`WildMatchBuilder`], which also supports
+//! rules for leading dots, unanchored matching and length limits:
+//! ``` rust
+//! # extern crate wildmatch; use wildmatch::WildMatchBuilder;
+//! let m = WildMatchBuilder::new().escape('\\').braces(true).build(r"\*.{md,txt}").unwrap();
+//! assert!(m.matches("*.md"));
+//! ```
+//!
+//! Occurrences of a pattern within a larger string can be located with
+//! [`WildMatchPattern::find`] and [`WildMatchPattern::find_iter`]:
+//! ``` rust
+//! # extern crate wildmatch; use wildmatch::WildMatch;
+//! assert_eq!(Some(4..7), WildMatch::new("c?t").find("the cat"));
+//! ```
+//!
+//! Many patterns can be matched against the same input with a [`WildMatchSet`]:
+//! ``` rust
+//! # extern crate wildmatch; use wildmatch::{WildMatch, WildMatchSet};
+//! let set: WildMatchSet = ["*.rs", "*.toml", "src/*"].iter().map(|p| WildMatch::new(p)).collect();
+//! assert_eq!(vec![0, 2], set.matches("src/lib.rs"));
+//! ```
+//!
+//! The crate supports `#![no_std]` environments with an allocator if the
+//! default `std` feature is disabled. Matching paths and OS strings requires
+//! the `std` feature.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
-use std::fmt;
+mod builder;
+mod captures;
+mod class;
+mod dynamic;
+mod engine;
+mod error;
+mod find;
+mod nfa;
+mod parse;
+mod set;
+mod shift_and;
+mod translate;
 
+pub use builder::{WildMatchBuilder, WildMatchPatternBuilder};
+pub use captures::Captures;
+pub use dynamic::{DynWildMatch, WildMatchOptions};
+pub use error::{PatternError, PatternErrorKind, TranslateError};
+pub use find::FindIter;
+pub use nfa::WildMatchNfa;
+pub use set::{WildMatchPatternSet, WildMatchSet};
+pub use translate::SqlLike;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Range;
+use core::str::{Chars, FromStr};
+use engine::{contains_ignore_ascii_case, Backtrack, Bytes, Matcher};
+use parse::{Parser, Program, Shape, Syntax, Token};
+#[cfg(feature = "std")]
+use std::ffi::OsStr;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use alloc::string::ToString;
+#[cfg(feature = "serde")]
+use core::convert::TryFrom;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -77,22 +180,102 @@ pub type WildMatch = WildMatchPattern<'*', '?'>;
 /// // ✅ Compiles fine.
 /// WildMatchPattern::<'*', '?'>::new_case_insensitive("");
 /// ```
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "SerializedPattern", into = "SerializedPattern")
+)]
+#[derive(Debug, Clone, Default)]
 pub struct WildMatchPattern<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> {
-    pattern: Vec<char>,
+    program: Program,
     case_insensitive: bool,
 }
 
+/// Layout used to (de)serialize a [`WildMatchPattern`]. Case-sensitive
+/// patterns with the default syntax are plain pattern strings, all others are
+/// objects with the pattern string and only the options which are set.
+/// Patterns serialized by version 2.6 and earlier are objects with the pattern
+/// as an array of chars.
+///
+/// Deserialized patterns are compiled again like with a [`WildMatchBuilder`],
+/// so that they are simplified like patterns created with
+/// [`WildMatchPattern::new`] and invalid patterns or options are rejected.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SerializedPattern {
+    Pattern(String),
+    Options {
+        pattern: String,
+        #[serde(default, skip_serializing_if = "parse::is_false")]
+        case_insensitive: bool,
+        #[serde(flatten)]
+        syntax: Syntax,
+    },
+    Legacy {
+        pattern: Vec<char>,
+        case_insensitive: bool,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> TryFrom<SerializedPattern>
+    for WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+    type Error = String;
+
+    fn try_from(serialized: SerializedPattern) -> Result<Self, Self::Error> {
+        let (pattern, case_insensitive, syntax) = match serialized {
+            SerializedPattern::Pattern(pattern) => (pattern, false, Syntax::default()),
+            SerializedPattern::Options {
+                pattern,
+                case_insensitive,
+                syntax,
+            } => (pattern, case_insensitive, syntax),
+            SerializedPattern::Legacy {
+                pattern,
+                case_insensitive,
+            } => (
+                pattern.into_iter().collect(),
+                case_insensitive,
+                Syntax::default(),
+            ),
+        };
+        let is_wildcard = |c: char| c == MULTI_WILDCARD || c == SINGLE_WILDCARD;
+        if syntax.escape.is_some_and(is_wildcard) {
+            return Err(String::from("escape character cannot be a wildcard"));
+        }
+        if syntax.separators.iter().any(|&c| is_wildcard(c)) {
+            return Err(String::from("path separator cannot be a wildcard"));
+        }
+        let mut m = Self::try_compile(&pattern, syntax, None).map_err(|e| e.to_string())?;
+        m.set_case_insensitive(case_insensitive);
+        Ok(m)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char>
+    From<WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>> for SerializedPattern
+{
+    fn from(m: WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>) -> Self {
+        let pattern = String::from(m.pattern());
+        if !m.case_insensitive && *m.syntax() == Syntax::default() {
+            return SerializedPattern::Pattern(pattern);
+        }
+        SerializedPattern::Options {
+            pattern,
+            case_insensitive: m.case_insensitive,
+            syntax: m.syntax().clone(),
+        }
+    }
+}
+
 impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> fmt::Display
     for WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use std::fmt::Write;
-        for c in &self.pattern {
-            f.write_char(*c)?;
-        }
-        Ok(())
+        f.write_str(self.pattern())
     }
 }
 
@@ -106,35 +289,7 @@ impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char>
 
     /// Constructor with pattern which can be used for matching.
     pub fn new(pattern: &str) -> WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD> {
-        #[allow(clippy::let_unit_value)]
-        let _ = Self::WILDCARDS_DIFFER;
-
-        let mut simplified: Vec<char> = pattern.chars().collect();
-        let mut new_len = simplified.len();
-        let mut wildcard_count = 0;
-
-        for idx in (0..simplified.len()).rev() {
-            if simplified[idx] == MULTI_WILDCARD {
-                wildcard_count += 1;
-            } else {
-                if wildcard_count > 1 {
-                    new_len -= wildcard_count - 1;
-                    simplified[idx + 1..].rotate_left(wildcard_count - 1);
-                }
-                wildcard_count = 0;
-            }
-        }
-        if wildcard_count > 1 {
-            new_len -= wildcard_count - 1;
-            simplified.rotate_left(wildcard_count - 1);
-        }
-
-        simplified.truncate(new_len);
-
-        Self {
-            pattern: simplified,
-            case_insensitive: false,
-        }
+        Self::compile(pattern, Syntax::default())
     }
 
     /// Constructor with pattern which can be used for matching with case-insensitive comparison.
@@ -142,10 +297,170 @@ impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char>
         pattern: &str,
     ) -> WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD> {
         let mut m = Self::new(pattern);
-        m.case_insensitive = true;
+        m.set_case_insensitive(true);
         m
     }
 
+    /// Constructor with pattern which uses `\` as escape character.
+    ///
+    /// An escaped character always matches literally, even if it is one of the
+    /// wildcards or the escape character itself. A trailing escape character
+    /// without a following character matches itself.
+    pub fn new_escaped(pattern: &str) -> WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD> {
+        Self::new_with_escape(pattern, '\\')
+    }
+
+    /// Constructor with pattern which uses the given `escape` character to
+    /// match the following character literally.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `escape` is one of the wildcard characters.
+    pub fn new_with_escape(
+        pattern: &str,
+        escape: char,
+    ) -> WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD> {
+        assert!(
+            escape != MULTI_WILDCARD && escape != SINGLE_WILDCARD,
+            "escape character cannot be a wildcard"
+        );
+        Self::compile(
+            pattern,
+            Syntax {
+                escape: Some(escape),
+                ..Syntax::default()
+            },
+        )
+    }
+
+    /// Constructor with pattern which supports bracket character classes.
+    ///
+    /// - `[abc]` matches one of the characters `a`, `b` or `c`.
+    /// - `[a-z]` matches one character in the inclusive range from `a` to `z`.
+    /// - `[!a-z]` matches one character which is not in the given range.
+    ///
+    /// A `]` directly after the opening bracket (or `!`) and a `-` as first or
+    /// last member of the class are matched literally. Wildcards inside a class
+    /// have no special meaning. A `[` without closing `]` matches itself.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// let m = WildMatch::new_with_classes("log[0-9].txt");
+    /// assert!(m.matches("log7.txt"));
+    /// assert!(!m.matches("logs.txt"));
+    /// assert!(WildMatch::new_with_classes("[!.]*").matches("visible"));
+    /// ```
+    pub fn new_with_classes(pattern: &str) -> WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD> {
+        Self::compile(
+            pattern,
+            Syntax {
+                classes: true,
+                ..Syntax::default()
+            },
+        )
+    }
+
+    /// Constructor with pattern which supports brace alternations.
+    ///
+    /// `{a,b}` matches either the branch `a` or the branch `b`. Branches can
+    /// contain wildcards and can be nested up to 64 levels deep. A `{` without
+    /// closing `}` matches itself, as do `,` and `}` outside of an alternation.
+    /// Alternations which are nested deeper match themselves literally.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// let m = WildMatch::new_with_braces("*.{jpg,png,gif}");
+    /// assert!(m.matches("cat.png"));
+    /// assert!(!m.matches("cat.txt"));
+    /// assert!(WildMatch::new_with_braces("{src/*,tests/{unit,it}/*}.rs").matches("tests/it/a.rs"));
+    /// ```
+    pub fn new_with_braces(pattern: &str) -> WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD> {
+        Self::compile(
+            pattern,
+            Syntax {
+                braces: true,
+                ..Syntax::default()
+            },
+        )
+    }
+
+    /// Constructor with pattern which matches paths separated by any of the
+    /// given `separators`.
+    ///
+    /// In path mode the single- and multi-character wildcards never match a
+    /// separator. Two multi-wildcards which form a whole path segment, like in
+    /// `src/**/*.rs`, match zero or more whole path components. At the end of
+    /// the pattern they match everything below a directory. A separator in the
+    /// pattern matches any of the `separators`.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// let m = WildMatch::new_with_separators("src/**/*.rs", &['/']);
+    /// assert!(m.matches("src/lib.rs"));
+    /// assert!(m.matches("src/a/b/lib.rs"));
+    /// assert!(!WildMatch::new_with_separators("src/*.rs", &['/']).matches("src/a/lib.rs"));
+    /// assert!(WildMatch::new_with_separators("src/*.rs", &['/', '\\']).matches(r"src\lib.rs"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the `separators` is a wildcard character.
+    pub fn new_with_separators(
+        pattern: &str,
+        separators: &[char],
+    ) -> WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD> {
+        assert!(
+            !separators.contains(&MULTI_WILDCARD) && !separators.contains(&SINGLE_WILDCARD),
+            "path separator cannot be a wildcard"
+        );
+        Self::compile(
+            pattern,
+            Syntax {
+                separators: separators.to_vec(),
+                ..Syntax::default()
+            },
+        )
+    }
+
+    /// Fallible constructor with pattern which can be used for matching.
+    ///
+    /// Fails with a [`PatternError`] instead of matching invalid syntax
+    /// literally. With only the two wildcards every pattern is valid, so this
+    /// never fails. Errors are returned by
+    /// [`try_new_with_max_len`](Self::try_new_with_max_len) for too long
+    /// patterns and by [`WildMatchBuilder::build`] for patterns with escapes,
+    /// character classes or alternations. Patterns are also parsed like this
+    /// with [`str::parse`]:
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// let m: WildMatch = "*.rs".parse().unwrap();
+    /// assert!(m.matches("lib.rs"));
+    /// assert_eq!(m, WildMatch::try_new("*.rs").unwrap());
+    /// ```
+    pub fn try_new(
+        pattern: &str,
+    ) -> Result<WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>, PatternError> {
+        Self::try_compile(pattern, Syntax::default(), None)
+    }
+
+    /// Fallible constructor which fails if the pattern has more than `max_len`
+    /// characters, e.g. to limit the size of patterns from untrusted input.
+    ///
+    /// ```
+    /// # use wildmatch::{PatternErrorKind, WildMatch};
+    /// assert!(WildMatch::try_new_with_max_len("*.rs", 4).is_ok());
+    /// let error = WildMatch::try_new_with_max_len("*.toml", 4).unwrap_err();
+    /// assert_eq!(PatternErrorKind::TooLong { max_len: 4 }, error.kind());
+    /// assert_eq!(4, error.offset());
+    /// ```
+    pub fn try_new_with_max_len(
+        pattern: &str,
+        max_len: usize,
+    ) -> Result<WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>, PatternError> {
+        Self::try_compile(pattern, Syntax::default(), Some(max_len))
+    }
+
     #[deprecated(since = "2.0.0", note = "use `matches` instead")]
     pub fn is_match(&self, input: &str) -> bool {
         self.matches(input)
@@ -153,74 +468,500 @@ impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char>
 
     /// Returns true if pattern applies to the given input string
     pub fn matches(&self, input: &str) -> bool {
-        if self.pattern.is_empty() {
-            return input.is_empty();
-        }
-        let mut input_chars = input.chars();
-
-        let mut pattern_idx = 0;
-        if let Some(mut input_char) = input_chars.next() {
-            const NONE: usize = usize::MAX;
-            let mut start_idx = NONE;
-            let mut matched = "".chars();
-
-            loop {
-                if pattern_idx < self.pattern.len() && self.pattern[pattern_idx] == MULTI_WILDCARD {
-                    start_idx = pattern_idx;
-                    matched = input_chars.clone();
-                    pattern_idx += 1;
-                } else if pattern_idx < self.pattern.len()
-                    && (self.pattern[pattern_idx] == SINGLE_WILDCARD
-                        || self.pattern[pattern_idx] == input_char
-                        || (self.case_insensitive
-                            && self.pattern[pattern_idx].to_lowercase().collect::<Vec<_>>()
-                                == input_char.to_lowercase().collect::<Vec<_>>()))
-                {
-                    pattern_idx += 1;
-                    if let Some(next_char) = input_chars.next() {
-                        input_char = next_char;
-                    } else {
-                        break;
-                    }
-                } else if start_idx != NONE {
-                    pattern_idx = start_idx + 1;
-                    if let Some(next_char) = matched.next() {
-                        input_char = next_char;
-                    } else {
-                        break;
-                    }
-                    input_chars = matched.clone();
+        self.matcher().matches(input)
+    }
+
+    /// Returns for each of the inputs whether the pattern applies to it.
+    ///
+    /// Faster than calling [`matches`](Self::matches) for each input if most
+    /// inputs do not match, since inputs which lack the longest literal part of
+    /// the pattern are rejected by a substring search without running the
+    /// matcher. Case-insensitive patterns search for their longest ASCII part
+    /// ignoring ASCII case.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// let m = WildMatch::new("* ERROR *");
+    /// let lines = ["12:00 INFO started", "12:01 ERROR failed", "ERROR"];
+    /// assert_eq!(vec![false, true, false], m.matches_many(&lines));
+    /// ```
+    pub fn matches_many(&self, inputs: &[&str]) -> Vec<bool> {
+        let matcher = self.matcher();
+        let literal = match self.program.shape {
+            _ if self.case_insensitive => self.program.required_literal_ignore_case(),
+            Shape::Tokens | Shape::Contains => self.program.required_literal(),
+            // Other shapes are matched by a single comparison anyway.
+            _ => "",
+        };
+        inputs
+            .iter()
+            .map(|input| {
+                let found = if self.case_insensitive {
+                    contains_ignore_ascii_case(input.as_bytes(), literal.as_bytes())
                 } else {
-                    return false;
-                }
-            }
-        }
+                    input.contains(literal)
+                };
+                found && matcher.matches(*input)
+            })
+            .collect()
+    }
+
+    /// Returns true if pattern applies to the given bytes, which do not have to
+    /// be valid UTF-8.
+    ///
+    /// Literal characters of the pattern match their UTF-8 encoding and the
+    /// single-character wildcard matches exactly one byte. Character classes and
+    /// case-insensitive comparison only match valid UTF-8 encoded characters.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// assert!(WildMatch::new("*.log").matches_bytes(b"\xff\xfe.log"));
+    /// assert!(WildMatch::new("a?c").matches_bytes(b"a\x80c"));
+    /// assert!(!WildMatch::new("a?c").matches_bytes("aöc".as_bytes()));
+    /// ```
+    pub fn matches_bytes(&self, input: &[u8]) -> bool {
+        self.matcher().matches(&Bytes {
+            bytes: input,
+            utf8: false,
+        })
+    }
+
+    /// Returns true if pattern applies to the given bytes, which do not have to
+    /// be valid UTF-8.
+    ///
+    /// Like [`matches_bytes`](Self::matches_bytes), but the single-character
+    /// wildcard matches a whole character where the input is valid UTF-8 and a
+    /// single byte elsewhere.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// assert!(WildMatch::new("a?c").matches_bytes_utf8("aöc".as_bytes()));
+    /// assert!(WildMatch::new("a?c").matches_bytes_utf8(b"a\x80c"));
+    /// ```
+    pub fn matches_bytes_utf8(&self, input: &[u8]) -> bool {
+        self.matcher().matches(&Bytes {
+            bytes: input,
+            utf8: true,
+        })
+    }
+
+    /// Compiles the pattern into an automaton which matches the same inputs
+    /// without backtracking, however many wildcards the pattern has. See
+    /// [`WildMatchNfa`].
+    pub fn to_nfa(&self) -> WildMatchNfa {
+        WildMatchNfa::new(&self.program, self.syntax(), self.case_insensitive)
+    }
+
+    /// Returns true if pattern applies to the given path.
+    ///
+    /// The path is matched like with [`matches_os_str`](Self::matches_os_str),
+    /// so paths which are not valid Unicode can be matched as well.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// # use std::path::Path;
+    /// assert!(WildMatch::new("src/*.rs").matches_path(Path::new("src/lib.rs")));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn matches_path(&self, path: &Path) -> bool {
+        self.matches_os_str(path.as_os_str())
+    }
+
+    /// Returns true if pattern applies to the given OS string.
+    ///
+    /// The string is matched in its platform-native encoding without a lossy
+    /// conversion to UTF-8, like with [`matches_bytes_utf8`](Self::matches_bytes_utf8).
+    /// Parts which are not valid Unicode can only be matched by wildcards,
+    /// where the single-character wildcard matches a single byte.
+    #[cfg(feature = "std")]
+    pub fn matches_os_str(&self, input: &OsStr) -> bool {
+        self.matches_bytes_utf8(input.as_encoded_bytes())
+    }
 
-        while pattern_idx < self.pattern.len() && self.pattern[pattern_idx] == MULTI_WILDCARD {
-            pattern_idx += 1;
+    /// Returns what each wildcard of the pattern matched if the pattern applies
+    /// to the given input string.
+    ///
+    /// There is one capture per single- and multi-character wildcard, `**` path
+    /// segment and character class in the order they appear in the pattern.
+    /// Wildcards in branches of an alternation which are not part of the match
+    /// have no capture.
+    ///
+    /// If the input can be matched in several ways, the wildcards are assigned
+    /// from left to right and each one consumes as few characters as possible
+    /// (leftmost-shortest). Of the branches of an alternation the first one
+    /// which leads to a match is taken.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// let captures = WildMatch::new("backup-*.tar.gz").captures("backup-2024-01-31.tar.gz").unwrap();
+    /// assert_eq!(Some("2024-01-31"), captures.get(0));
+    /// assert_eq!(Some(7..17), captures.range(0));
+    ///
+    /// let captures = WildMatch::new("*-*").captures("a-b-c").unwrap();
+    /// assert_eq!(vec![Some("a"), Some("b-c")], captures.iter().collect::<Vec<_>>());
+    /// ```
+    pub fn captures<'t>(&self, input: &'t str) -> Option<Captures<'t>> {
+        // Inputs which do not match are rejected by the faster matcher
+        // before searching for the spans.
+        if !self.matches(input) {
+            return None;
+        }
+        let mut backtrack = Backtrack::new(self.matcher(), input, true);
+        backtrack.record_spans();
+        if !backtrack.matches_at(0, 0) {
+            return None;
         }
+        let written = self.program.pattern_tokens(self.syntax());
+        let spans = self.program.tokens[written.clone()]
+            .iter()
+            .zip(backtrack.into_spans().drain(written))
+            .filter(|(token, _)| token.is_wildcard())
+            .map(|(_, span)| span)
+            .collect();
+        Some(Captures::new(input, spans))
+    }
+
+    /// Returns the byte range of the first match of the pattern within the
+    /// haystack.
+    ///
+    /// The match starting at the lowest offset is returned. As for
+    /// [`captures`](Self::captures), its wildcards consume as few characters as
+    /// possible, so a trailing multi-wildcard matches the empty string.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// let haystack = "ERROR 42: disk full, ERROR 7: retry";
+    /// assert_eq!(Some(0..9), WildMatch::new("ERROR *:").find(haystack));
+    /// assert_eq!(None, WildMatch::new("WARN*").find(haystack));
+    /// ```
+    pub fn find(&self, haystack: &str) -> Option<Range<usize>> {
+        self.find_iter(haystack).next()
+    }
+
+    /// Returns an iterator over the byte ranges of all non-overlapping matches
+    /// of the pattern within the haystack. Each match is found like with
+    /// [`find`](Self::find) after the end of the previous match.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// let haystack = "ERROR 42: disk full, ERROR 7: retry";
+    /// let errors = WildMatch::new("ERROR *:")
+    ///     .find_iter(haystack)
+    ///     .map(|range| &haystack[range])
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(vec!["ERROR 42:", "ERROR 7:"], errors);
+    /// ```
+    pub fn find_iter<'p, 't>(&'p self, haystack: &'t str) -> FindIter<'p, 't> {
+        let prefix = match self.program.tokens.first() {
+            Some(&Token::Literal(start, end)) if !self.case_insensitive => {
+                self.program.literal(start, end)
+            }
+            _ => "",
+        };
+        FindIter::new(
+            Backtrack::new(self.matcher(), haystack, false),
+            haystack,
+            prefix,
+        )
+    }
+
+    /// Returns a regular expression which matches the same inputs as the
+    /// pattern.
+    ///
+    /// The regex is anchored at the start and end of the input and literal
+    /// characters are escaped. Case-insensitive patterns set the `i` flag and
+    /// wildcards match line breaks with the `s` flag. Only inline flags,
+    /// non-capturing groups and plain character classes are used, so the regex
+    /// works with the [`regex`](https://docs.rs/regex) crate as well as engines
+    /// like PCRE or RE2.
+    ///
+    /// The `i` flag ignores case by Unicode simple case folding, while the
+    /// pattern compares the lowercase of chars which lowercase to a single
+    /// char and lets classes match the lowercase and uppercase of a char. So
+    /// for a few non-ASCII chars a case-insensitive regex differs from the
+    /// pattern: `σ` matches the final sigma `ς` only in the regex, and `[a-z]`
+    /// matches `İ`, whose lowercase is `i` with a combining dot, only in the
+    /// pattern.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// assert_eq!(r"(?s)^Lorem.ipsum.*\.$", WildMatch::new("Lorem?ipsum*.").to_regex());
+    /// assert_eq!(r"(?is)^.*\.rs$", WildMatch::new_case_insensitive("*.rs").to_regex());
+    /// ```
+    pub fn to_regex(&self) -> String {
+        translate::to_regex(&self.program, self.syntax(), self.case_insensitive)
+    }
+
+    /// Returns a pattern for the SQL `LIKE` operator which matches the same
+    /// inputs, using `escape` to escape literal `%`, `_` and `escape` characters.
+    ///
+    /// Whether `LIKE` compares case-insensitively depends on the database and
+    /// its collation, so case-insensitive patterns may need `ILIKE` or `LOWER`.
+    /// Fails if the pattern uses syntax without an equivalent in `LIKE`, like
+    /// character classes, alternations or wildcards in path mode.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatch;
+    /// let like = WildMatch::new("100%*").to_sql_like('\\').unwrap();
+    /// assert_eq!(r"100\%%", like.pattern());
+    /// assert_eq!(r"'100\%%' ESCAPE '\'", like.to_string());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `escape` is `%` or `_`.
+    pub fn to_sql_like(&self, escape: char) -> Result<SqlLike, TranslateError> {
+        assert!(
+            escape != '%' && escape != '_',
+            "escape character cannot be a wildcard"
+        );
+        translate::to_sql_like(&self.program, self.syntax(), escape)
+    }
+
+    /// Returns a pattern with other wildcard characters which matches the same
+    /// inputs and keeps all other syntax.
+    ///
+    /// Literal characters which are wildcards of the target are escaped with
+    /// the escape character of the pattern. Fails if there is none, if the
+    /// escape character or a path separator is a wildcard of the target, or if
+    /// a wildcard of the target is part of the enabled class or brace syntax.
+    ///
+    /// ```
+    /// # use wildmatch::{TranslateError, WildMatch, WildMatchPattern};
+    /// let m = WildMatch::new_with_escape("100%*", '\\');
+    /// let sql = m.to_dialect::<'%', '_'>().unwrap();
+    /// assert_eq!(r"100\%%", sql.pattern());
+    /// assert!(sql.matches("100% done"));
+    ///
+    /// let m = WildMatch::new("100%*");
+    /// assert_eq!(
+    ///     Err(TranslateError::EscapeRequired { literal: '%' }),
+    ///     m.to_dialect::<'%', '_'>()
+    /// );
+    /// ```
+    pub fn to_dialect<const MULTI: char, const SINGLE: char>(
+        &self,
+    ) -> Result<WildMatchPattern<MULTI, SINGLE>, TranslateError> {
+        #[allow(clippy::let_unit_value)]
+        let _ = WildMatchPattern::<MULTI, SINGLE>::WILDCARDS_DIFFER;
 
-        // If we have reached the end of both the pattern and the text, the pattern matches the text.
-        pattern_idx == self.pattern.len()
+        translate::check_wildcards(&self.program, self.syntax(), MULTI, SINGLE)?;
+        let mut m = WildMatchPattern::from_program(self.program.clone());
+        m.set_case_insensitive(self.case_insensitive);
+        Ok(m)
     }
 
     /// Returns the pattern string.
-    /// N.B. Consecutive multi-wildcards are simplified to a single multi-wildcard.
-    pub fn pattern(&self) -> String {
-        self.pattern.iter().collect::<String>()
+    /// N.B. Consecutive multi-wildcards are simplified to a single multi-wildcard and
+    /// only characters which would otherwise have a special meaning stay escaped.
+    pub fn pattern(&self) -> &str {
+        self.program.pattern()
     }
 
-    /// Returns the pattern string as a slice of chars.
-    pub fn pattern_chars(&self) -> &[char] {
-        &self.pattern
+    /// Returns an iterator over the chars of the pattern string.
+    pub fn pattern_chars(&self) -> Chars<'_> {
+        self.pattern().chars()
     }
 
     /// Returns if the pattern is case-insensitive.
     pub fn is_case_insensitive(&self) -> bool {
         self.case_insensitive
     }
+
+    /// Returns the escape character of the pattern, if any.
+    pub fn escape_char(&self) -> Option<char> {
+        self.syntax().escape
+    }
+
+    /// Returns if bracket character classes are supported by the pattern.
+    pub fn has_classes(&self) -> bool {
+        self.syntax().classes
+    }
+
+    /// Returns if brace alternations are supported by the pattern.
+    pub fn has_braces(&self) -> bool {
+        self.syntax().braces
+    }
+
+    /// Returns the sorted path separators of the pattern. Empty if the pattern
+    /// is not matched in path mode.
+    pub fn separators(&self) -> &[char] {
+        &self.syntax().separators
+    }
+
+    /// Returns if a `.` at the start of the input or of a path segment can only
+    /// be matched by a literal `.` in the pattern.
+    pub fn has_literal_leading_dot(&self) -> bool {
+        self.syntax().literal_leading_dot
+    }
+
+    /// Returns if matches have to start at the start of the input.
+    pub fn is_anchored_start(&self) -> bool {
+        !self.syntax().unanchored_start
+    }
+
+    /// Returns if matches have to end at the end of the input.
+    pub fn is_anchored_end(&self) -> bool {
+        !self.syntax().unanchored_end
+    }
+
+    fn compile(pattern: &str, syntax: Syntax) -> WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD> {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::WILDCARDS_DIFFER;
+
+        let program = Parser::new(pattern, MULTI_WILDCARD, SINGLE_WILDCARD, &syntax).parse();
+        Self::from_program(program)
+    }
+
+    /// Compiles the pattern and fails on invalid syntax or if the pattern has
+    /// more than `max_len` chars.
+    fn try_compile(
+        pattern: &str,
+        syntax: Syntax,
+        max_len: Option<usize>,
+    ) -> Result<WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>, PatternError> {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::WILDCARDS_DIFFER;
+
+        if let Some(max_len) = max_len {
+            if pattern.chars().nth(max_len).is_some() {
+                return Err(PatternError::new(
+                    PatternErrorKind::TooLong { max_len },
+                    max_len,
+                ));
+            }
+        }
+        let program = Parser::new(pattern, MULTI_WILDCARD, SINGLE_WILDCARD, &syntax).try_parse()?;
+        Ok(Self::from_program(program))
+    }
+
+    fn from_program(mut program: Program) -> WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD> {
+        program.set_pattern(MULTI_WILDCARD, SINGLE_WILDCARD);
+        Self {
+            program,
+            case_insensitive: false,
+        }
+    }
+
+    fn syntax(&self) -> &Syntax {
+        self.program.syntax()
+    }
+
+    /// Sets whether characters are compared case-insensitively and folds the
+    /// literals of the pattern if so.
+    fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        if case_insensitive {
+            self.program.fold_case();
+        }
+        self.case_insensitive = case_insensitive;
+    }
+
+    fn matcher(&self) -> Matcher<'_> {
+        Matcher {
+            program: &self.program,
+            syntax: self.syntax(),
+            case_insensitive: self.case_insensitive,
+        }
+    }
+
+    /// Returns what identifies the pattern. The program is derived from it.
+    fn key(&self) -> (&str, bool, &Syntax) {
+        (self.pattern(), self.case_insensitive, self.syntax())
+    }
 }
 
+impl WildMatchPattern<'%', '_'> {
+    /// Constructor from a pattern of the SQL `LIKE` operator with the
+    /// character of its `ESCAPE` clause, if any.
+    ///
+    /// Like in SQL, the pattern is invalid if it ends with the escape
+    /// character.
+    ///
+    /// ```
+    /// # use wildmatch::WildMatchPattern;
+    /// let m = WildMatchPattern::from_sql_like(r"100\%%", Some('\\')).unwrap();
+    /// assert!(m.matches("100% done"));
+    /// assert!(!m.matches("1000"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `escape` is `%` or `_`.
+    pub fn from_sql_like(pattern: &str, escape: Option<char>) -> Result<Self, PatternError> {
+        assert!(
+            escape != Some('%') && escape != Some('_'),
+            "escape character cannot be a wildcard"
+        );
+        let syntax = Syntax {
+            escape,
+            ..Syntax::default()
+        };
+        Self::try_compile(pattern, syntax, None)
+    }
+}
+
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> FromStr
+    for WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+    type Err = PatternError;
+
+    fn from_str(pattern: &str) -> Result<Self, PatternError> {
+        Self::try_new(pattern)
+    }
+}
+
+/// Patterns are equal if their simplified pattern strings and their options
+/// are equal, so that patterns which were written differently but match the
+/// same inputs, like `a**b` and `a*b`, can be deduplicated:
+///
+/// ```
+/// # use wildmatch::WildMatch;
+/// # use std::collections::HashSet;
+/// let patterns: HashSet<_> = ["a**b", "a*b", "*.rs"].iter().map(|p| WildMatch::new(p)).collect();
+/// assert_eq!(2, patterns.len());
+/// ```
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> PartialEq
+    for WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> Eq
+    for WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+}
+
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> Hash
+    for WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+/// Patterns are ordered by their simplified pattern strings first and by their
+/// options second.
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> Ord
+    for WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> PartialOrd
+    for WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares by matching, i.e. `m == "input"` is the same as
+/// [`m.matches("input")`](WildMatchPattern::matches). Prefer the named method,
+/// which cannot be mistaken for a comparison of the pattern string.
 impl<'a, const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> PartialEq<&'a str>
     for WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>
 {
@@ -234,8 +975,16 @@ mod tests {
     use super::*;
     use ntest::assert_false;
     use ntest::test_case;
+    use parse::Token;
     use rand::{distributions::Alphanumeric, Rng};
 
+    /// Returns a string of at most `max_len` chars picked from the alphabet.
+    pub(crate) fn random_string(rng: &mut impl Rng, alphabet: &[char], max_len: usize) -> String {
+        (0..rng.gen_range(0..=max_len))
+            .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+            .collect()
+    }
+
     #[test]
     fn is_match_random() {
         const PATTERN_LEN: usize = 100;
@@ -284,6 +1033,124 @@ mod tests {
                     pattern,
                     input
                 );
+                assert!(m.matches_bytes_utf8(input.as_bytes()));
+                assert!(matches_chars(&pattern, &input, false));
+            }
+        }
+    }
+
+    /// The original matcher, which walks the chars of pattern and input.
+    fn matches_chars(pattern: &str, input: &str, case_insensitive: bool) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let input: Vec<char> = input.chars().collect();
+        let chars_eq = |a: char, b: char| {
+            a == b || (case_insensitive && a.to_lowercase().eq(b.to_lowercase()))
+        };
+        let (mut pattern_idx, mut input_idx) = (0, 0);
+        let mut star: Option<(usize, usize)> = None;
+        while input_idx < input.len() {
+            if pattern.get(pattern_idx) == Some(&'*') {
+                star = Some((pattern_idx, input_idx));
+                pattern_idx += 1;
+            } else if pattern
+                .get(pattern_idx)
+                .is_some_and(|&c| c == '?' || chars_eq(c, input[input_idx]))
+            {
+                pattern_idx += 1;
+                input_idx += 1;
+            } else if let Some((star_idx, star_end)) = star {
+                star = Some((star_idx, star_end + 1));
+                pattern_idx = star_idx + 1;
+                input_idx = star_end + 1;
+            } else {
+                return false;
+            }
+        }
+        pattern[pattern_idx..].iter().all(|&c| c == '*')
+    }
+
+    #[test]
+    fn is_match_like_chars() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1_000 {
+            let pattern = random_string(&mut rng, &['a', 'Ä', 'ß', '€', '😀', '*', '?'], 8);
+            let case_insensitive = rand::random();
+            let m = if case_insensitive {
+                WildMatch::new_case_insensitive(&pattern)
+            } else {
+                WildMatch::new(&pattern)
+            };
+            for _ in 0..20 {
+                let input = random_string(&mut rng, &['a', 'A', 'ä', 'Ä', 'ß', '€', '😀'], 10);
+                let expected = matches_chars(&pattern, &input, case_insensitive);
+                assert_eq!(expected, m.matches(&input), "{} {}", pattern, input);
+                assert_eq!(
+                    expected,
+                    m.matches_bytes_utf8(input.as_bytes()),
+                    "{} {}",
+                    pattern,
+                    input
+                );
+            }
+        }
+    }
+
+    #[test_case("*ab*ab", "abab", true)]
+    #[test_case("*aab", "aaab", true)]
+    #[test_case("*ab?", "abab", false)]
+    #[test_case("a*a", "a", false)]
+    #[test_case("*ä*b", "bäb", true)]
+    #[test_case("*ab*", "xxaxbx", false)]
+    #[test_case("*ab", "ab", true)]
+    fn is_match_literal_search(pattern: &str, input: &str, expected: bool) {
+        let m = WildMatch::new(pattern);
+        assert_eq!(expected, m.matches(input));
+        assert_eq!(expected, m.matches_bytes(input.as_bytes()));
+        assert_eq!(expected, m.matches_bytes_utf8(input.as_bytes()));
+    }
+
+    #[test]
+    fn matches_many_like_matches() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1_000 {
+            let pattern = random_string(&mut rng, &['a', 'b', 'k', 'ä', ' ', '.', '*', '?'], 8);
+            let m = if rand::random() {
+                WildMatch::new_case_insensitive(&pattern)
+            } else {
+                WildMatch::new(&pattern)
+            };
+            let inputs: Vec<String> = (0..20)
+                .map(|_| {
+                    random_string(
+                        &mut rng,
+                        &['a', 'b', 'B', 'K', '\u{212a}', 'ä', ' ', '.'],
+                        20,
+                    )
+                })
+                .collect();
+            let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+            let expected: Vec<bool> = inputs.iter().map(|input| m.matches(input)).collect();
+            assert_eq!(expected, m.matches_many(&inputs), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn literal_search_like_backtracking() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1_000 {
+            let pattern = random_string(&mut rng, &['a', 'b', 'ä', '*', '?'], 8);
+            let m = WildMatch::new(&pattern);
+            for _ in 0..20 {
+                let input = random_string(&mut rng, &['a', 'b', 'ä'], 10);
+                let expected = Backtrack::new(m.matcher(), input.as_str(), true).matches_at(0, 0);
+                assert_eq!(expected, m.matches(&input), "{} {}", pattern, input);
+                assert_eq!(
+                    expected,
+                    m.matches_bytes_utf8(input.as_bytes()),
+                    "{} {}",
+                    pattern,
+                    input
+                );
             }
         }
     }
@@ -315,9 +1182,23 @@ mod tests {
     #[test_case("К*", "кОт", name = "cyrillic_mixed2")]
     #[test_case("К?*", "кОТ", name = "cyrillic_mixed3")]
     #[test_case("К**", "коТ", name = "cyrillic_mixed4")]
+    #[test_case("*ΛΌΓΟΣ", "Ο λόγοσ", name = "greek")]
+    #[test_case("STRASSE?", "strasse1", name = "ascii_after_fold")]
+    #[test_case("*İ?", "xİa", name = "lowercase_of_several_chars")]
     fn is_match_case_insensitive(pattern: &str, input: &str) {
         let m = WildMatch::new_case_insensitive(pattern);
         assert!(m.matches(input));
+        assert!(m.matches_bytes_utf8(input.as_bytes()));
+    }
+
+    #[test_case("КОТ", "кит")]
+    #[test_case("CAT", "CA")]
+    #[test_case("ca", "CAT")]
+    #[test_case("*Ä", "a")]
+    #[test_case("İ", "i\u{307}", name = "no_match_lowercase_of_several_chars")]
+    fn is_match_case_insensitive_false(pattern: &str, input: &str) {
+        let m = WildMatch::new_case_insensitive(pattern);
+        assert_false!(m.matches(input));
     }
 
     #[test_case("*d*")]
@@ -499,4 +1380,804 @@ mod tests {
         let m = WildMatch::new("");
         assert_eq!("", m.to_string());
     }
+
+    #[test_case(r"what\?.txt", "what?.txt")]
+    #[test_case(r"\*", "*")]
+    #[test_case(r"a\\b", r"a\b")]
+    #[test_case(r"\a\b", "ab")]
+    #[test_case(r"*\*", "foo*")]
+    #[test_case(r"\**", "*foo")]
+    #[test_case(r"\?*\?", "??")]
+    #[test_case(r"dangling\", r"dangling\")]
+    fn match_escaped(pattern: &str, input: &str) {
+        let m = WildMatch::new_escaped(pattern);
+        assert!(m.matches(input), "Expected {} to match {}", pattern, input);
+    }
+
+    #[test_case(r"what\?.txt", "whats.txt")]
+    #[test_case(r"\*", "foo")]
+    #[test_case(r"\*", "")]
+    #[test_case(r"a\\b", "ab")]
+    #[test_case(r"*\*", "foo")]
+    #[test_case(r"\**", "foo*")]
+    fn no_match_escaped(pattern: &str, input: &str) {
+        let m = WildMatch::new_escaped(pattern);
+        assert_false!(m.matches(input));
+    }
+
+    #[test]
+    fn escape_is_literal_without_escape_mode() {
+        assert!(WildMatch::new(r"a\*").matches(r"a\bc"));
+        assert_false!(WildMatch::new(r"a\*").matches("a*"));
+    }
+
+    #[test]
+    fn custom_escape_char() {
+        let m = WildMatchPattern::<'%', '_'>::new_with_escape("100#%_", '#');
+        assert!(m.matches("100%x"));
+        assert_false!(m.matches("1000x"));
+        assert_eq!(Some('#'), m.escape_char());
+        assert_eq!(None, WildMatch::new("").escape_char());
+    }
+
+    #[test]
+    #[should_panic]
+    fn escape_char_cannot_be_wildcard() {
+        WildMatch::new_with_escape("", '*');
+    }
+
+    #[test_case(r"what\?.txt")]
+    #[test_case(r"\*\?\\")]
+    #[test_case(r"a*\**b")]
+    fn to_string_escaped(pattern: &str) {
+        let m = WildMatch::new_escaped(pattern);
+        assert_eq!(pattern, m.to_string());
+        assert_eq!(m, WildMatch::new_escaped(m.pattern()));
+    }
+
+    #[test_case("log[0-9].txt", "log7.txt")]
+    #[test_case("[abc]*", "banana")]
+    #[test_case("*[!.]", "file.rs")]
+    #[test_case("[]]", "]")]
+    #[test_case("[*]?", "*?")]
+    #[test_case("*[a-c][x-z]", "abcz")]
+    #[test_case("[abc", "[abc")]
+    #[test_case("a]", "a]")]
+    fn match_classes(pattern: &str, input: &str) {
+        let m = WildMatch::new_with_classes(pattern);
+        assert!(m.matches(input), "Expected {} to match {}", pattern, input);
+    }
+
+    #[test_case("log[0-9].txt", "logs.txt")]
+    #[test_case("[abc]*", "dog")]
+    #[test_case("*[!.]", "file.")]
+    #[test_case("[*]", "a")]
+    #[test_case("[a-c]", "")]
+    #[test_case("[abc", "a")]
+    fn no_match_classes(pattern: &str, input: &str) {
+        let m = WildMatch::new_with_classes(pattern);
+        assert_false!(m.matches(input));
+    }
+
+    #[test]
+    fn classes_are_literal_without_class_syntax() {
+        assert!(WildMatch::new("[ab]").matches("[ab]"));
+        assert_false!(WildMatch::new("[ab]").matches("a"));
+        assert!(!WildMatch::new("").has_classes());
+        assert!(WildMatch::new_with_classes("").has_classes());
+    }
+
+    #[test_case("LOG[0-9].TXT", "log1.txt")]
+    #[test_case("[a-c]AT", "Cat")]
+    #[test_case("[!A-C]at", "dat")]
+    fn match_classes_case_insensitive(pattern: &str, input: &str) {
+        let mut m = WildMatch::new_with_classes(pattern);
+        m.set_case_insensitive(true);
+        assert!(m.matches(input));
+    }
+
+    #[test_case("log[0-9].txt")]
+    #[test_case("[!]a-]*")]
+    #[test_case("[abc")]
+    fn to_string_classes(pattern: &str) {
+        let m = WildMatch::new_with_classes(pattern);
+        assert_eq!(pattern, m.to_string());
+        assert_eq!(m, WildMatch::new_with_classes(m.pattern()));
+    }
+
+    #[test_case("*.{jpg,png,gif}", "cat.gif")]
+    #[test_case("{a,ab}c", "abc")]
+    #[test_case("{a*,b}x", "ayyx")]
+    #[test_case("{,pre}fix", "fix")]
+    #[test_case("{,pre}fix", "prefix")]
+    #[test_case("a{b,c{d,e}}f", "acef")]
+    #[test_case("{*a,*b}{*a,*b}", "xxbxa")]
+    #[test_case("{?,??}{x,xx}", "aax")]
+    #[test_case("{}", "")]
+    #[test_case("{a}", "a")]
+    #[test_case("{a,b", "{a,b")]
+    #[test_case("a,b}", "a,b}")]
+    #[test_case("{a,{b}", "{a,b")]
+    fn match_braces(pattern: &str, input: &str) {
+        let m = WildMatch::new_with_braces(pattern);
+        assert!(m.matches(input), "Expected {} to match {}", pattern, input);
+    }
+
+    #[test_case("*.{jpg,png,gif}", "cat.txt")]
+    #[test_case("*.{jpg,png,gif}", "cat.jpgpng")]
+    #[test_case("{a,ab}c", "ac_")]
+    #[test_case("a{b,c{d,e}}f", "acf")]
+    #[test_case("a{b,c{d,e}}f", "abdf")]
+    #[test_case("{?,??}{x,xx}", "aaaax")]
+    #[test_case("{a,b", "a")]
+    #[test_case("{}", "a")]
+    fn no_match_braces(pattern: &str, input: &str) {
+        let m = WildMatch::new_with_braces(pattern);
+        assert_false!(m.matches(input));
+    }
+
+    #[test]
+    fn braces_are_literal_without_brace_syntax() {
+        assert!(WildMatch::new("{a,b}").matches("{a,b}"));
+        assert_false!(WildMatch::new("{a,b}").matches("a"));
+        assert!(!WildMatch::new("").has_braces());
+        assert!(WildMatch::new_with_braces("").has_braces());
+    }
+
+    #[test]
+    fn braces_case_insensitive() {
+        let mut m = WildMatch::new_with_braces("*.{JPG,PNG}");
+        m.set_case_insensitive(true);
+        assert!(m.matches("cat.png"));
+        assert_false!(m.matches("cat.gif"));
+    }
+
+    #[test]
+    fn braces_nested_too_deep() {
+        let pattern = "{".repeat(200_000);
+        assert!(WildMatch::new_with_braces(&pattern).matches(&pattern));
+
+        let pattern = "{a,".repeat(100_000) + &"}".repeat(100_000);
+        let m = WildMatch::new_with_braces(&pattern);
+        assert!(m.matches("a"));
+        assert_eq!(pattern, m.pattern());
+        let error = WildMatchBuilder::new()
+            .braces(true)
+            .build(&pattern)
+            .unwrap_err();
+        assert_eq!(
+            PatternErrorKind::NestingTooDeep { max_depth: 64 },
+            error.kind()
+        );
+        assert_eq!(192, error.offset());
+
+        let nested = "{a,".repeat(64) + &"}".repeat(64);
+        assert!(WildMatchBuilder::new().braces(true).build(&nested).is_ok());
+    }
+
+    #[test]
+    fn braces_long_pattern() {
+        let m = WildMatch::new_with_braces(&"{a,b*}".repeat(100_000));
+        assert!(m.matches(&"ab".repeat(50_000)));
+        assert_false!(m.matches(&"a".repeat(100_001)));
+    }
+
+    #[test]
+    fn braces_long_input() {
+        let m = WildMatch::new_with_braces("{a,b}*a*b");
+        assert_false!(m.matches(&"a".repeat(100_000)));
+        assert!(m.matches(&format!("{}b", "a".repeat(100_000))));
+    }
+
+    #[test]
+    fn braces_many_stars() {
+        let m = WildMatch::new_with_braces(&"{*a,*b}".repeat(20));
+        assert_false!(m.matches(&"ab".repeat(9)));
+        assert!(m.matches(&"ab".repeat(10)));
+    }
+
+    #[test_case("*.{jpg,png,gif}")]
+    #[test_case("a{b,c{d,e}}f")]
+    #[test_case("{,}{}")]
+    #[test_case("{a,b")]
+    #[test_case("{a,{b}")]
+    #[test_case("a,b}")]
+    fn to_string_braces(pattern: &str) {
+        let m = WildMatch::new_with_braces(pattern);
+        assert_eq!(pattern, m.to_string());
+        assert_eq!(m, WildMatch::new_with_braces(m.pattern()));
+    }
+
+    #[test_case("src/*.rs", "src/lib.rs")]
+    #[test_case("src/**/*.rs", "src/lib.rs")]
+    #[test_case("src/**/*.rs", "src/a/b/lib.rs")]
+    #[test_case("**/*.rs", "lib.rs")]
+    #[test_case("**/*.rs", "a/b/lib.rs")]
+    #[test_case("**", "a/b/c")]
+    #[test_case("**", "")]
+    #[test_case("a/**", "a/b/c")]
+    #[test_case("a/**", "a/")]
+    #[test_case("a/**/b", "a/b")]
+    #[test_case("a/**/b", "a/x/y/b")]
+    #[test_case("a/**/**/b", "a/x/b")]
+    #[test_case("*/?/c", "a/b/c")]
+    #[test_case("a**b", "axxb")]
+    #[test_case("*x*/y", "axbxc/y")]
+    #[test_case("**/a*b/c", "x/axxb/c")]
+    fn match_path(pattern: &str, input: &str) {
+        let m = WildMatch::new_with_separators(pattern, &['/']);
+        assert!(m.matches(input), "Expected {} to match {}", pattern, input);
+    }
+
+    #[test_case("src/*.rs", "src/a/lib.rs")]
+    #[test_case("src/*", "src/a/b")]
+    #[test_case("src/**/*.rs", "src/a/lib.txt")]
+    #[test_case("src/**/*.rs", "lib.rs")]
+    #[test_case("a?b", "a/b")]
+    #[test_case("*", "a/b")]
+    #[test_case("a/**", "a")]
+    #[test_case("a/**/b", "a/xb")]
+    #[test_case("a/**/b", "ab")]
+    #[test_case("a**b", "a/b")]
+    #[test_case("**/*.rs", "a/b/")]
+    #[test_case("*x*/y", "ax/bxc/y")]
+    fn no_match_path(pattern: &str, input: &str) {
+        let m = WildMatch::new_with_separators(pattern, &['/']);
+        assert_false!(m.matches(input));
+    }
+
+    #[test]
+    fn path_multiple_separators() {
+        let m = WildMatch::new_with_separators("src/**/*.rs", &['/', '\\']);
+        assert!(m.matches(r"src\a/b\lib.rs"));
+        assert_false!(m.matches(r"src\a\lib.txt"));
+        assert_false!(WildMatch::new_with_separators("src?lib", &['/', '\\']).matches(r"src\lib"));
+        assert_eq!(&['/', '\\'], m.separators());
+        assert!(WildMatch::new("").separators().is_empty());
+    }
+
+    #[test]
+    fn path_classes_do_not_match_separators() {
+        let syntax = Syntax {
+            classes: true,
+            separators: vec!['/'],
+            ..Syntax::default()
+        };
+        let m = WildMatch::compile("a[!x]b", syntax);
+        assert!(m.matches("a-b"));
+        assert_false!(m.matches("a/b"));
+    }
+
+    #[test]
+    fn path_braces() {
+        let syntax = Syntax {
+            braces: true,
+            separators: vec!['/'],
+            ..Syntax::default()
+        };
+        let m = WildMatch::compile("{**/,src/}*.{rs,toml}", syntax);
+        assert!(m.matches("Cargo.toml"));
+        assert!(m.matches("a/b/main.rs"));
+        assert_false!(m.matches("a/b/main.txt"));
+    }
+
+    #[test]
+    fn path_long_input() {
+        let m = WildMatch::new_with_separators("**/a/**/c", &['/']);
+        assert_false!(m.matches(&"a/".repeat(50_000)));
+        assert!(m.matches(&format!("{}c", "a/".repeat(50_000))));
+        let m = WildMatch::new_with_separators("**/*a*b", &['/']);
+        assert_false!(m.matches(&"a".repeat(100_000)));
+    }
+
+    #[test]
+    fn path_linear_matches_backtracking() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let pattern = random_string(&mut rng, &['a', '.', '/', '*', '?'], 7);
+            let input = random_string(&mut rng, &['a', '.', '/'], 7);
+            let m = WildMatch::compile(
+                &pattern,
+                Syntax {
+                    separators: vec!['/'],
+                    literal_leading_dot: rand::random(),
+                    ..Syntax::default()
+                },
+            );
+            if m.program.is_linear() {
+                assert_eq!(
+                    Backtrack::new(m.matcher(), input.as_str(), true).matches_at(0, 0),
+                    m.matches(&input),
+                    "Pattern {} on input {}",
+                    pattern,
+                    input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn path_globstar_collapses_in_segment() {
+        assert_eq!(
+            "a*b",
+            WildMatch::new_with_separators("a**b", &['/']).to_string()
+        );
+    }
+
+    #[test]
+    fn separators_are_literal_without_path_mode() {
+        assert!(WildMatch::new("src/*.rs").matches("src/a/lib.rs"));
+        assert!(WildMatch::new("src/**/*.rs").matches("src/lib/a.rs"));
+        assert_false!(WildMatch::new("src/**/*.rs").matches("src/lib.rs"));
+    }
+
+    #[test_case("src/**/*.rs")]
+    #[test_case("**")]
+    #[test_case("a/**")]
+    #[test_case("**/a*b/**")]
+    fn to_string_path(pattern: &str) {
+        let m = WildMatch::new_with_separators(pattern, &['/']);
+        assert_eq!(pattern, m.to_string());
+        assert_eq!(m, WildMatch::new_with_separators(m.pattern(), &['/']));
+    }
+
+    #[test_case("backup-*.tar.gz", "backup-2024-01-31.tar.gz", "2024-01-31")]
+    #[test_case("*-*", "a-b-c", "a|b-c")]
+    #[test_case("*", "", "")]
+    #[test_case("c?t", "cat", "a")]
+    #[test_case("*?", "abc", "ab|c")]
+    #[test_case("?*?", "abc", "a|b|c")]
+    #[test_case("*a*", "banana", "b|nana")]
+    #[test_case("к*т", "кот", "о")]
+    fn captures(pattern: &str, input: &str, expected: &str) {
+        let captures = WildMatch::new(pattern).captures(input).unwrap();
+        let expected = expected.split('|').map(Some).collect::<Vec<_>>();
+        assert_eq!(expected, captures.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn captures_no_match() {
+        assert_eq!(None, WildMatch::new("*.rs").captures("lib.txt"));
+        assert_eq!(None, WildMatch::new("").captures("a"));
+    }
+
+    #[test]
+    fn captures_without_wildcards() {
+        let captures = WildMatch::new("cat").captures("cat").unwrap();
+        assert!(captures.is_empty());
+        assert_eq!(None, captures.get(0));
+        assert_eq!("cat", captures.input());
+    }
+
+    #[test]
+    fn captures_long_pattern() {
+        let m = WildMatch::new(&"*a".repeat(100_000));
+        let input = "xa".repeat(100_000);
+        let captures = m.captures(&input).unwrap();
+        assert_eq!(100_000, captures.len());
+        assert!(captures.iter().all(|capture| capture == Some("x")));
+        assert_eq!(None, m.captures(&"xa".repeat(99_999)));
+    }
+
+    #[test]
+    fn captures_ranges() {
+        let captures = WildMatch::new("ä*ö?").captures("äxyöü").unwrap();
+        assert_eq!(Some(2..4), captures.range(0));
+        assert_eq!(Some(6..8), captures.range(1));
+        assert_eq!(Some("ü"), captures.get(1));
+    }
+
+    #[test]
+    fn captures_with_syntax() {
+        let m = WildMatch::new_with_braces("*.{t*,r?}");
+        let captures = m.captures("lib.rs").unwrap();
+        assert_eq!(
+            vec![Some("lib"), None, Some("s")],
+            captures.iter().collect::<Vec<_>>()
+        );
+
+        let m = WildMatch::new_with_classes("log[0-9]*");
+        let captures = m.captures("log12").unwrap();
+        assert_eq!(
+            vec![Some("1"), Some("2")],
+            captures.iter().collect::<Vec<_>>()
+        );
+
+        let m = WildMatch::new_with_separators("src/**/*.rs", &['/']);
+        let captures = m.captures("src/a/b/lib.rs").unwrap();
+        assert_eq!(
+            vec![Some("a/b/"), Some("lib")],
+            captures.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn captures_reconstruct_input() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let pattern = random_string(&mut rng, &['a', 'b', '*', '?'], 7);
+            let input = random_string(&mut rng, &['a', 'b'], 7);
+            let m = WildMatch::new(&pattern);
+            let captures = m.captures(&input);
+            assert_eq!(m.matches(&input), captures.is_some());
+            if let Some(captures) = captures {
+                let mut captured = captures.iter();
+                let reconstructed = m
+                    .program
+                    .tokens
+                    .iter()
+                    .map(|&token| match token {
+                        Token::Literal(start, end) => m.program.literal(start, end).to_string(),
+                        _ => captured.next().unwrap().unwrap().to_string(),
+                    })
+                    .collect::<String>();
+                assert_eq!(input, reconstructed, "Pattern {}", pattern);
+            }
+        }
+    }
+
+    #[test_case("ERROR *:", "ERROR 42: disk full, ERROR 7: retry", "0..9|21..29")]
+    #[test_case("cat", "dog_cat_dog_cat", "4..7|12..15")]
+    #[test_case("c?t", "cat cut cot", "0..3|4..7|8..11")]
+    #[test_case("*.rs", "a.rs b.rs", "0..4|4..9")]
+    #[test_case("a*", "banana", "1..2|3..4|5..6")]
+    #[test_case("*", "ab", "0..0|1..1|2..2")]
+    #[test_case("", "ab", "0..0|1..1|2..2")]
+    #[test_case("ä?", "äöäü", "0..4|4..8")]
+    #[test_case("x", "abc", "")]
+    fn find_iter(pattern: &str, haystack: &str, expected: &str) {
+        let found = WildMatch::new(pattern)
+            .find_iter(haystack)
+            .map(|range| format!("{:?}", range))
+            .collect::<Vec<_>>();
+        assert_eq!(expected, found.join("|"));
+    }
+
+    #[test]
+    fn find() {
+        assert_eq!(Some(4..7), WildMatch::new("c?t").find("the cat"));
+        assert_eq!(None, WildMatch::new("dog").find("the cat"));
+        assert_eq!(Some(0..0), WildMatch::new("").find(""));
+        assert_eq!(None, WildMatch::new("?").find(""));
+    }
+
+    #[test]
+    fn find_with_syntax() {
+        let m = WildMatch::new_with_braces("{cat,dog}s");
+        assert_eq!(Some(8..12), m.find("hot dog dogs"));
+
+        let m = WildMatch::new_with_separators("src/*.rs", &['/']);
+        assert_eq!(Some(7..17), m.find("src/a/ src/lib.rs"));
+
+        let m = WildMatch::new_with_classes("[0-9][0-9]");
+        let found = m.find_iter("a1 22 333").collect::<Vec<_>>();
+        assert_eq!(vec![3..5, 6..8], found);
+    }
+
+    #[test]
+    fn find_case_insensitive() {
+        let m = WildMatch::new_case_insensitive("CAT");
+        assert_eq!(Some(4..7), m.find("the Cat"));
+    }
+
+    #[test]
+    fn find_long_haystack() {
+        let haystack = "a".repeat(100_000);
+        assert_eq!(None, WildMatch::new("a*b").find(&haystack));
+        assert_eq!(None, WildMatch::new("?a*b").find(&haystack));
+        assert_eq!(
+            Some(0..100_001),
+            WildMatch::new("a*b").find(&format!("{}b", haystack))
+        );
+        let line = "ERROR x ".repeat(10_000);
+        assert_eq!(None, WildMatch::new("ERROR *:").find(&line));
+    }
+
+    #[test]
+    fn find_matches_contained_pattern() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let pattern = random_string(&mut rng, &['a', 'b', '*', '?'], 7);
+            let haystack = random_string(&mut rng, &['a', 'b'], 7);
+            let m = WildMatch::new(&pattern);
+            let found = m.find(&haystack);
+            let contained = WildMatch::new(&format!("*{}*", pattern)).matches(&haystack);
+            assert_eq!(contained, found.is_some());
+            if let Some(range) = found {
+                assert!(m.matches(&haystack[range]));
+            }
+        }
+    }
+
+    #[test]
+    fn matches_invalid_utf8() {
+        let cases: &[(&str, &[u8])] = &[
+            ("*.log", b"\xff\xfe.log"),
+            ("a?c", b"a\x80c"),
+            ("*?", b"\xc3"),
+            ("h*o", b"h\xffll\xfeo"),
+        ];
+        for &(pattern, input) in cases {
+            assert!(WildMatch::new(pattern).matches_bytes(input));
+            assert!(WildMatch::new(pattern).matches_bytes_utf8(input));
+        }
+        assert_false!(WildMatch::new("a?c").matches_bytes(b"a\xff\xffc"));
+        assert_false!(WildMatch::new("*.log").matches_bytes(b"\xff.lo\xff"));
+    }
+
+    #[test]
+    fn classes_skip_invalid_utf8() {
+        let m = WildMatch::new_with_classes("[!a]b");
+        assert_false!(m.matches_bytes(&[0xff, b'b']));
+        assert!(m.matches_bytes("öb".as_bytes()));
+    }
+
+    #[test_case("a?c", "aöc", false, true)]
+    #[test_case("a??c", "aöc", true, false)]
+    #[test_case("?", "€", false, true)]
+    #[test_case("???", "€", true, false)]
+    #[test_case("a*", "aö", true, true)]
+    fn matches_bytes_single_wildcard(pattern: &str, input: &str, bytes: bool, utf8: bool) {
+        let m = WildMatch::new(pattern);
+        assert_eq!(bytes, m.matches_bytes(input.as_bytes()));
+        assert_eq!(utf8, m.matches_bytes_utf8(input.as_bytes()));
+    }
+
+    #[test]
+    fn matches_bytes_utf8_partial_sequence() {
+        // A truncated sequence is matched byte by byte.
+        let m = WildMatch::new("a??b");
+        assert!(m.matches_bytes_utf8(&[b'a', 0xe2, 0x82, b'b']));
+        assert_false!(m.matches_bytes_utf8(&[b'a', 0xe2, 0x82, 0xac, b'b']));
+    }
+
+    #[test]
+    fn matches_bytes_case_insensitive() {
+        let m = WildMatch::new_case_insensitive("*.LOG");
+        assert!(m.matches_bytes(b"\xff.log"));
+        assert_false!(m.matches_bytes(b"\xff.lo\xff"));
+    }
+
+    #[test]
+    fn matches_bytes_path_mode() {
+        let m = WildMatch::new_with_separators("src/**/*.rs", &['/']);
+        assert!(m.matches_bytes(b"src/\xff/a/lib.rs"));
+        assert_false!(m.matches_bytes(b"src/\xff/a\xfe"));
+        assert_false!(WildMatch::new_with_separators("a?b", &['/']).matches_bytes(b"a/b"));
+    }
+
+    #[test]
+    fn matches_bytes_like_matches() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let pattern = random_string(&mut rng, &['a', 'ö', '/', '*', '?', '{', ',', '}'], 7);
+            let input = random_string(&mut rng, &['a', 'ö', 'A', 'Ö', '/'], 7);
+            let mut m = WildMatch::compile(
+                &pattern,
+                Syntax {
+                    braces: true,
+                    separators: vec!['/'],
+                    ..Syntax::default()
+                },
+            );
+            m.set_case_insensitive(rand::random());
+            assert_eq!(
+                m.matches(&input),
+                m.matches_bytes_utf8(input.as_bytes()),
+                "Pattern {} on input {}",
+                pattern,
+                input
+            );
+            if input.is_ascii() {
+                assert_eq!(m.matches(&input), m.matches_bytes(input.as_bytes()));
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test_case("src/*.rs", "src/lib.rs", true)]
+    #[test_case("src/**/*.rs", "src/a/b/lib.rs", true)]
+    #[test_case("*.rs", "src/lib.rs", false)]
+    #[test_case("?ä?", "aäb", true)]
+    fn matches_path(pattern: &str, path: &str, expected: bool) {
+        let m = WildMatch::new_with_separators(pattern, &['/']);
+        assert_eq!(expected, m.matches_path(Path::new(path)));
+        assert_eq!(expected, m.matches_os_str(OsStr::new(path)));
+    }
+
+    #[cfg(all(feature = "std", unix))]
+    #[test]
+    fn matches_non_unicode_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"logs/\xff\xfe.log"));
+        assert!(WildMatch::new("logs/*.log").matches_path(path));
+        assert!(WildMatch::new("logs/??.log").matches_path(path));
+        assert_false!(WildMatch::new("logs/?.log").matches_path(path));
+    }
+
+    #[test_case("*.rs")]
+    #[test_case("a**b?")]
+    #[test_case("[a{b\\")]
+    #[test_case("")]
+    fn try_new_like_new(pattern: &str) {
+        assert_eq!(Ok(WildMatch::new(pattern)), WildMatch::try_new(pattern));
+        assert_eq!(Ok(WildMatch::new(pattern)), pattern.parse::<WildMatch>());
+    }
+
+    #[test_case("", 0)]
+    #[test_case("äöü*", 4)]
+    fn try_new_with_max_len(pattern: &str, max_len: usize) {
+        assert_eq!(
+            Ok(WildMatch::new(pattern)),
+            WildMatch::try_new_with_max_len(pattern, max_len)
+        );
+        let error = WildMatch::try_new_with_max_len(&format!("{}?", pattern), max_len).unwrap_err();
+        assert_eq!(PatternErrorKind::TooLong { max_len }, error.kind());
+        assert_eq!(max_len, error.offset());
+    }
+
+    #[test]
+    fn to_string_escaped_simplified() {
+        assert_eq!("ab", WildMatch::new_escaped(r"\a\b").to_string());
+        assert_eq!(r"\*\**", WildMatch::new_escaped(r"\*\***").to_string());
+        assert_eq!(r"a\\", WildMatch::new_escaped(r"a\").to_string());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test_case("*.rs", r#""*.rs""#)]
+    #[test_case("a**b", r#""a*b""#)]
+    #[test_case("", r#""""#)]
+    fn serialize_as_string(pattern: &str, expected: &str) {
+        let m = WildMatch::new(pattern);
+        assert_eq!(expected, serde_json::to_string(&m).unwrap());
+        let deserialized: WildMatch = serde_json::from_str(expected).unwrap();
+        assert_eq!(m, deserialized);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_simplifies_like_new() {
+        let m: WildMatch = serde_json::from_str(r#""a***b""#).unwrap();
+        assert_eq!(WildMatch::new("a***b"), m);
+        assert_eq!("a*b", m.pattern());
+        let m: WildMatch = serde_json::from_str(r#"{"pattern":"a**b"}"#).unwrap();
+        assert_eq!(WildMatch::new("a*b"), m);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_with_options() {
+        let m = WildMatch::new_case_insensitive("*.RS");
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(r#"{"pattern":"*.RS","case_insensitive":true}"#, json);
+        assert_eq!(m, serde_json::from_str::<WildMatch>(&json).unwrap());
+
+        let m = WildMatchBuilder::new()
+            .escape('\\')
+            .braces(true)
+            .separators(&['/'])
+            .anchor_end(false)
+            .build(r"src/**/\*.{rs,toml}")
+            .unwrap();
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(
+            r#"{"pattern":"src/**/\\*.{rs,toml}","escape":"\\","braces":true,"separators":["/"],"unanchored_end":true}"#,
+            json
+        );
+        assert_eq!(m, serde_json::from_str::<WildMatch>(&json).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_invalid() {
+        assert!(serde_json::from_str::<WildMatch>("42").is_err());
+        assert!(serde_json::from_str::<WildMatch>(r#"{"case_insensitive":true}"#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test_case(
+        r#"{"pattern":"a*","escape":"*"}"#,
+        "escape character cannot be a wildcard"
+    )]
+    #[test_case(
+        r#"{"pattern":"a*","escape":"?"}"#,
+        "escape character cannot be a wildcard"
+    )]
+    #[test_case(
+        r#"{"pattern":"a*","separators":["*"]}"#,
+        "path separator cannot be a wildcard"
+    )]
+    #[test_case(r#"{"pattern":"[a","classes":true}"#, "unterminated character class")]
+    #[test_case(r#"{"pattern":"{a","braces":true}"#, "unterminated alternation")]
+    #[test_case(r#"{"pattern":"a%","escape":"%"}"#, "dangling escape character")]
+    fn deserialize_invalid_options(json: &str, message: &str) {
+        let error = serde_json::from_str::<WildMatch>(json).unwrap_err();
+        assert!(error.to_string().contains(message), "{}", error);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_legacy() {
+        let json = r#"{"pattern":["*",".","r","s"],"case_insensitive":false}"#;
+        let m = serde_json::from_str::<WildMatch>(json).unwrap();
+        assert_eq!(WildMatch::new("*.rs"), m);
+        assert_eq!(r#""*.rs""#, serde_json::to_string(&m).unwrap());
+
+        let json = r#"{"pattern":["*","*","R","S"],"case_insensitive":true}"#;
+        let m = serde_json::from_str::<WildMatch>(json).unwrap();
+        assert_eq!(WildMatch::new_case_insensitive("*RS"), m);
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(r#"{"pattern":"*RS","case_insensitive":true}"#, json);
+        assert_eq!(m, serde_json::from_str::<WildMatch>(&json).unwrap());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn plain_patterns_are_small() {
+        assert_eq!(64, core::mem::size_of::<WildMatch>());
+        assert_eq!(72, core::mem::size_of::<DynWildMatch>());
+    }
+
+    #[test]
+    fn eq_and_hash_by_normalized_pattern() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |m: &WildMatch| {
+            let mut hasher = DefaultHasher::new();
+            m.hash(&mut hasher);
+            hasher.finish()
+        };
+        let a = WildMatch::new("a**b");
+        let b = WildMatch::new("a*b");
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(
+            WildMatch::new("a*b"),
+            WildMatch::new_case_insensitive("a*b")
+        );
+        assert_ne!(WildMatch::new("{a,b}"), WildMatch::new_with_braces("{a,b}"));
+        assert_eq!(
+            WildMatch::new_with_escape(r"\a\*", '\\'),
+            WildMatch::new_with_escape(r"a\*", '\\')
+        );
+        assert_eq!(
+            WildMatch::new_with_separators("src/*", &['/', '\\', '/']),
+            WildMatch::new_with_separators("src/*", &['\\', '/'])
+        );
+    }
+
+    #[test]
+    fn patterns_as_keys() {
+        use std::collections::{BTreeMap, HashSet};
+        let patterns = ["*.rs", "b?", "*.rs", "a**", "a*"];
+        let set: HashSet<_> = patterns.iter().map(|p| WildMatch::new(p)).collect();
+        assert_eq!(3, set.len());
+
+        let map: BTreeMap<_, _> = patterns
+            .iter()
+            .enumerate()
+            .map(|(idx, p)| (WildMatch::new(p), idx))
+            .collect();
+        let keys = map.keys().map(|m| m.pattern()).collect::<Vec<_>>();
+        assert_eq!(vec!["*.rs", "a*", "b?"], keys);
+        assert_eq!(Some(&4), map.get(&WildMatch::new("a***")));
+    }
+
+    #[test]
+    fn ord_by_pattern_then_options() {
+        let mut patterns = vec![
+            WildMatch::new_case_insensitive("a"),
+            WildMatch::new("b"),
+            WildMatch::new("a"),
+        ];
+        patterns.sort();
+        assert_eq!(
+            vec![
+                WildMatch::new("a"),
+                WildMatch::new_case_insensitive("a"),
+                WildMatch::new("b")
+            ],
+            patterns
+        );
+    }
 }
@@ -0,0 +1,471 @@
+//! Matching without backtracking by simulating a nondeterministic automaton.
+
+use crate::class::CharClass;
+use crate::engine::fold;
+use crate::parse::{Program, Syntax, Token};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A state of the automaton. Unless noted otherwise, a state which consumes
+/// an input char moves on to the following state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// Consumes the char. Folded in case-insensitive automata.
+    Char(char),
+    /// Consumes any char except a separator.
+    Single,
+    /// Consumes a char of the class with the given index.
+    Class(usize),
+    /// Consumes a separator.
+    Separator,
+    /// Consumes chars except separators and stays, or moves on without
+    /// consuming.
+    Multi,
+    /// Consumes any chars except leading dots and stays, or moves on without
+    /// consuming.
+    GlobStar,
+    /// Consumes any chars and stays, or moves on without consuming.
+    Skip,
+    /// Start of a path component. Moves on without consuming past the
+    /// following [`State::Component`], stays on a separator and moves into the
+    /// component on any other char.
+    Components,
+    /// Within a path component. Moves back to the start of the next component
+    /// on a separator and stays on any other char.
+    Component,
+    /// Moves to the start of each branch of the alternation with the given
+    /// index without consuming.
+    Alt(usize),
+    /// Moves to the state of the token with the given index without consuming.
+    Jump(usize),
+}
+
+/// Rows of [`WildMatchNfa::masks`] for states which consume input, or move on
+/// without consuming it, regardless of the input char.
+const SINGLE: usize = 0;
+const SEPARATOR: usize = 1;
+const MULTI: usize = 2;
+const GLOB_STAR: usize = 3;
+const SKIP: usize = 4;
+const COMPONENTS: usize = 5;
+const COMPONENT: usize = 6;
+/// States which move on to the following state without consuming.
+const EPSILON: usize = 7;
+/// The first state of each run of consecutive [`EPSILON`] states.
+const EPSILON_START: usize = 8;
+/// States which move to other states without consuming, see
+/// [`WildMatchNfa::jumps`].
+const JUMP: usize = 9;
+/// Number of the rows above. The rows of the classes follow, then the rows of
+/// the literal chars.
+const ROWS: usize = 10;
+
+/// A pattern compiled into an automaton which matches without backtracking.
+///
+/// [`WildMatchPattern::matches`](crate::WildMatchPattern::matches) backtracks
+/// when a multi-wildcard consumed too little input, which takes quadratic time
+/// in the worst case. The automaton instead tracks all states the pattern can
+/// be in at once as a set of bits, so every input char is only looked at once
+/// and costs a few passes over the set. Matching thus takes time proportional
+/// to the length of the input times the length of the pattern divided by 64.
+/// This is faster for patterns with many wildcards which partially match the
+/// input often, but slower for typical patterns.
+///
+/// Created with [`WildMatchPattern::to_nfa`](crate::WildMatchPattern::to_nfa).
+/// Matches the same inputs as the pattern it is created from:
+///
+/// ```
+/// # use wildmatch::WildMatch;
+/// let m = WildMatch::new_case_insensitive("*a*b*c*d*e*f*").to_nfa();
+/// assert!(m.matches("xAxBxCxDxExFx"));
+/// assert!(!m.matches(&"abcde".repeat(1000)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct WildMatchNfa {
+    /// Sets of states, `words` words each. The rows are indexed by the
+    /// constants above.
+    masks: Vec<u64>,
+    words: usize,
+    /// The accepting state, after all other states.
+    accepting: usize,
+    /// Sorted chars of the literal states. Folded in case-insensitive
+    /// automata.
+    chars: Vec<char>,
+    classes: Vec<CharClass>,
+    /// Moves without consuming which do not lead to the following state, as
+    /// pairs of source and target state sorted by the source.
+    jumps: Vec<(usize, usize)>,
+    syntax: Syntax,
+    case_insensitive: bool,
+}
+
+impl WildMatchNfa {
+    pub(crate) fn new(program: &Program, syntax: &Syntax, case_insensitive: bool) -> Self {
+        // Index of the first state of each token and of the accepting state.
+        let mut starts = Vec::with_capacity(program.tokens.len() + 1);
+        let mut states = Vec::new();
+        for (idx, &token) in program.tokens.iter().enumerate() {
+            starts.push(states.len());
+            match token {
+                Token::Literal(start, end) => {
+                    let (start, end) = if case_insensitive {
+                        program.folded()[idx]
+                    } else {
+                        (start, end)
+                    };
+                    states.extend(program.literal(start, end).chars().map(State::Char));
+                }
+                Token::Single => states.push(State::Single),
+                Token::Multi => states.push(State::Multi),
+                Token::Class(class) => states.push(State::Class(class as usize)),
+                Token::Alt(alt) => states.push(State::Alt(alt as usize)),
+                Token::Jump(target) => states.push(State::Jump(target as usize)),
+                Token::Separator(_) => states.push(State::Separator),
+                Token::Components(_) => {
+                    states.push(State::Components);
+                    states.push(State::Component);
+                }
+                Token::GlobStar => states.push(State::GlobStar),
+                Token::Skip => states.push(State::Skip),
+            }
+        }
+        starts.push(states.len());
+
+        let mut chars: Vec<char> = states
+            .iter()
+            .filter_map(|state| match *state {
+                State::Char(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+        chars.sort_unstable();
+        chars.dedup();
+        let classes = program.classes().to_vec();
+        let words = (states.len() + 1).div_ceil(64);
+        let mut masks = vec![0; (ROWS + classes.len() + chars.len()) * words];
+        let mut jumps = Vec::new();
+        for (state, &kind) in states.iter().enumerate() {
+            let row = match kind {
+                State::Char(c) => ROWS + classes.len() + chars.binary_search(&c).unwrap(),
+                State::Single => SINGLE,
+                State::Class(class) => ROWS + class,
+                State::Separator => SEPARATOR,
+                State::Multi => MULTI,
+                State::GlobStar => GLOB_STAR,
+                State::Skip => SKIP,
+                State::Components => COMPONENTS,
+                State::Component => COMPONENT,
+                State::Alt(_) | State::Jump(_) => JUMP,
+            };
+            masks[row * words + state / 64] |= 1 << (state % 64);
+            match kind {
+                State::Multi | State::GlobStar | State::Skip => {
+                    masks[EPSILON * words + state / 64] |= 1 << (state % 64)
+                }
+                State::Components => jumps.push((state, state + 2)),
+                State::Alt(alt) => jumps.extend(
+                    program.alternations()[alt]
+                        .branches
+                        .iter()
+                        .map(|&branch| (state, starts[branch])),
+                ),
+                State::Jump(target) => jumps.push((state, starts[target])),
+                _ => {}
+            }
+        }
+        for &(state, _) in &jumps {
+            masks[JUMP * words + state / 64] |= 1 << (state % 64);
+        }
+        let mut carry = 0;
+        for word in 0..words {
+            let epsilon = masks[EPSILON * words + word];
+            masks[EPSILON_START * words + word] = epsilon & !(epsilon << 1 | carry);
+            carry = epsilon >> 63;
+        }
+
+        WildMatchNfa {
+            masks,
+            words,
+            accepting: states.len(),
+            chars,
+            classes,
+            jumps,
+            syntax: syntax.clone(),
+            case_insensitive,
+        }
+    }
+
+    /// Returns true if pattern applies to the given input string
+    pub fn matches(&self, input: &str) -> bool {
+        let mut current = vec![0; self.words];
+        current[0] = 1;
+        self.close(&mut current);
+        let mut next = vec![0; self.words];
+        let mut classes = Vec::new();
+        // Whether the next char starts the input or a path segment.
+        let mut segment_start = true;
+        for c in input.chars() {
+            let separator = self.syntax.is_separator(c);
+            let hidden = self.syntax.literal_leading_dot && c == '.' && segment_start;
+            let wildcard = !separator && !hidden;
+            let folded = if self.case_insensitive { fold(c) } else { c };
+            let chars = self
+                .chars
+                .binary_search(&folded)
+                .ok()
+                .map(|idx| ROWS + self.classes.len() + idx);
+            classes.clear();
+            if wildcard {
+                classes.extend(
+                    (0..self.classes.len())
+                        .filter(|&class| self.classes[class].matches(c, self.case_insensitive))
+                        .map(|class| ROWS + class),
+                );
+            }
+            // All bits set if the condition holds.
+            let separator_bits = (separator as u64).wrapping_neg();
+            let visible_bits = (!hidden as u64).wrapping_neg();
+            let wildcard_bits = (wildcard as u64).wrapping_neg();
+
+            // States which consume the char and move on to the following
+            // state, and states which consume it and stay.
+            let mut carry = 0;
+            for word in 0..self.words {
+                let mask = |row: usize| self.masks[row * self.words + word];
+                let mut advance = wildcard_bits & (mask(SINGLE) | mask(COMPONENTS))
+                    | separator_bits & (mask(SEPARATOR) | mask(COMPONENT));
+                if let Some(row) = chars {
+                    advance |= mask(row);
+                }
+                for &row in &classes {
+                    advance |= mask(row);
+                }
+                let stay = mask(SKIP)
+                    | wildcard_bits & (mask(MULTI) | mask(COMPONENT))
+                    | visible_bits & mask(GLOB_STAR)
+                    | separator_bits & mask(COMPONENTS);
+                let advance = current[word] & advance;
+                next[word] = advance << 1 | carry | current[word] & stay;
+                carry = advance >> 63;
+            }
+            // A separator ends a path component and moves back to the start
+            // of the next one.
+            if separator {
+                let mut borrow = 0;
+                for word in (0..self.words).rev() {
+                    let back = current[word] & self.masks[COMPONENT * self.words + word];
+                    next[word] |= back >> 1 | borrow;
+                    borrow = back << 63;
+                }
+            }
+            self.close(&mut next);
+            if next.iter().all(|&word| word == 0) {
+                return false;
+            }
+            core::mem::swap(&mut current, &mut next);
+            segment_start = separator;
+        }
+        current[self.accepting / 64] & 1 << (self.accepting % 64) != 0
+    }
+
+    /// Adds the states reachable without consuming input to `set`.
+    fn close(&self, set: &mut [u64]) {
+        let epsilon = &self.masks[EPSILON * self.words..(EPSILON + 1) * self.words];
+        let starts = &self.masks[EPSILON_START * self.words..(EPSILON_START + 1) * self.words];
+        let jumps = &self.masks[JUMP * self.words..(JUMP + 1) * self.words];
+        loop {
+            // Within a run of epsilon states, every state from the first one
+            // in the set on is reachable. Adding the start of the run to its
+            // states which are not in the set carries up to exactly that
+            // state, so all runs are followed at once.
+            let mut carry = false;
+            let mut shifted = 0;
+            for word in 0..self.words {
+                let missing = epsilon[word] & !set[word];
+                let (sum, overflow) = missing.overflowing_add(starts[word]);
+                let (sum, carried) = sum.overflowing_add(carry as u64);
+                carry = overflow || carried;
+                let reached = epsilon[word] & !(sum ^ missing) | epsilon[word] & set[word];
+                set[word] |= reached | reached << 1 | shifted;
+                shifted = reached >> 63;
+            }
+
+            let mut changed = false;
+            for word in 0..self.words {
+                let mut bits = set[word] & jumps[word];
+                while bits != 0 {
+                    let state = word * 64 + bits.trailing_zeros() as usize;
+                    bits &= bits - 1;
+                    let first = self.jumps.partition_point(|&(source, _)| source < state);
+                    for &(_, target) in self.jumps[first..]
+                        .iter()
+                        .take_while(|&&(source, _)| source == state)
+                    {
+                        if set[target / 64] & 1 << (target % 64) == 0 {
+                            set[target / 64] |= 1 << (target % 64);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            if !changed {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::random_string;
+    use crate::{WildMatch, WildMatchBuilder};
+    use ntest::{assert_false, test_case};
+    use rand::Rng;
+
+    #[test_case("*a*b*c", "xaxbxc", true)]
+    #[test_case("*a*b*c", "xaxbxcx", false)]
+    #[test_case("", "", true)]
+    #[test_case("?", "ä", true)]
+    #[test_case("*", "", true)]
+    #[test_case("a?c*", "abcde", true)]
+    fn matches(pattern: &str, input: &str, expected: bool) {
+        assert_eq!(expected, WildMatch::new(pattern).to_nfa().matches(input));
+    }
+
+    #[test]
+    fn matches_path() {
+        let m = WildMatchBuilder::new()
+            .separators(&['/'])
+            .literal_leading_dot(true)
+            .braces(true)
+            .build("src/**/{*.rs,mod.?}")
+            .unwrap()
+            .to_nfa();
+        assert!(m.matches("src/lib.rs"));
+        assert!(m.matches("src/a/b/mod.c"));
+        assert_false!(m.matches("src/.a/lib.rs"));
+        assert_false!(m.matches("src/a/.x.rs"));
+        assert_false!(m.matches("src/a/mod.rs/"));
+    }
+
+    #[test]
+    fn matches_case_insensitive() {
+        let m = WildMatch::new_case_insensitive("*İ?Ä*").to_nfa();
+        assert!(m.matches("xİaäx"));
+        assert!(m.matches("İbÄ"));
+        assert_false!(m.matches("xi\u{307}aäx"));
+        assert_false!(m.matches("IbÄ"));
+    }
+
+    #[test]
+    fn matches_many_wildcards() {
+        let m = WildMatch::new(&format!("{}*b", "*a".repeat(100))).to_nfa();
+        assert!(m.matches(&format!("{}b", "a".repeat(100))));
+        assert_false!(m.matches(&"a".repeat(10_000)));
+    }
+
+    #[test]
+    fn matches_long_literal() {
+        let m = WildMatch::new(&"ab".repeat(20_000)).to_nfa();
+        assert_false!(m.matches("ababx"));
+        let literal = "ab".repeat(1_000);
+        let m = WildMatch::new(&format!("{}*", literal)).to_nfa();
+        assert!(m.matches(&format!("{}b", literal)));
+        assert_false!(m.matches(&literal[1..]));
+    }
+
+    #[test]
+    fn matches_like_pattern_across_words() {
+        let mut rng = rand::thread_rng();
+        let parts = [
+            ("a", "a"),
+            ("b", "b"),
+            (".", "."),
+            ("/", "/"),
+            ("?", "b"),
+            ("*", "ab"),
+            ("*", ""),
+            ("/**/", "/a/b/"),
+            ("/**/", "/"),
+            ("[!b]", "a"),
+            ("{a,*b}", "a"),
+            ("{a,*b}", "aab"),
+        ];
+        for _ in 0..500 {
+            let mut pattern = String::new();
+            let mut input = String::new();
+            for _ in 0..rng.gen_range(0..80) {
+                let (part, matched) = parts[rng.gen_range(0..parts.len())];
+                pattern.push_str(part);
+                input.push_str(matched);
+            }
+            let m = WildMatchBuilder::new()
+                .separators(&['/'])
+                .literal_leading_dot(rng.gen())
+                .classes(true)
+                .braces(true)
+                .build(&pattern)
+                .unwrap();
+            let nfa = m.to_nfa();
+            for _ in 0..10 {
+                let mut input = input.clone().into_bytes();
+                if !input.is_empty() && rng.gen() {
+                    let idx = rng.gen_range(0..input.len());
+                    input[idx] = b"ab./"[rng.gen_range(0..4)];
+                }
+                let input = String::from_utf8(input).unwrap();
+                assert_eq!(
+                    m.matches(&input),
+                    nfa.matches(&input),
+                    "Pattern {:?} on input {:?}",
+                    pattern,
+                    input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_like_pattern() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..5_000 {
+            let pattern = random_string(
+                &mut rng,
+                &[
+                    'a', 'B', 'ä', '.', '/', '*', '?', '[', ']', '!', '-', '{', ',', '}', '\\',
+                ],
+                9,
+            );
+            let flags = rand::random::<u8>();
+            let mut builder = WildMatchBuilder::new()
+                .case_insensitive(flags & 1 != 0)
+                .classes(flags & 2 != 0)
+                .braces(flags & 4 != 0)
+                .literal_leading_dot(flags & 8 != 0)
+                .anchor_start(flags & 16 == 0)
+                .anchor_end(flags & 32 == 0);
+            if flags & 64 != 0 {
+                builder = builder.separators(&['/']);
+            }
+            if flags & 128 != 0 {
+                builder = builder.escape('\\');
+            }
+            let m = match builder.build(&pattern) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            let nfa = m.to_nfa();
+            for _ in 0..10 {
+                let input = random_string(&mut rng, &['a', 'b', 'B', 'Ä', '.', '/', '-'], 7);
+                assert_eq!(
+                    m.matches(&input),
+                    nfa.matches(&input),
+                    "Pattern {:?} ({:?}) on input {:?}",
+                    pattern,
+                    m,
+                    input
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,1023 @@
+//! Compilation of pattern strings into tokens and rendering them back.
+
+use crate::class::CharClass;
+use crate::engine::fold;
+use crate::error::{PatternError, PatternErrorKind};
+use crate::shift_and::ShiftAnd;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::str::Chars;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Optional pattern syntax which is applied when parsing a pattern.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub(crate) struct Syntax {
+    /// Character which makes the following character match literally.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) escape: Option<char>,
+    /// Whether `[...]` denotes a character class.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_false"))]
+    pub(crate) classes: bool,
+    /// Whether `{a,b}` denotes an alternation.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_false"))]
+    pub(crate) braces: bool,
+    /// Path separators. If not empty, wildcards do not match separators and
+    /// `**` matches across them.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub(crate) separators: Vec<char>,
+    /// Whether a `.` at the start of the input or of a path segment is only
+    /// matched by a literal `.`.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_false"))]
+    pub(crate) literal_leading_dot: bool,
+    /// Whether matches may start anywhere in the input.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_false"))]
+    pub(crate) unanchored_start: bool,
+    /// Whether matches may end anywhere in the input.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_false"))]
+    pub(crate) unanchored_end: bool,
+}
+
+/// Syntax of programs which do not store any other.
+static DEFAULT_SYNTAX: Syntax = Syntax {
+    escape: None,
+    classes: false,
+    braces: false,
+    separators: Vec::new(),
+    literal_leading_dot: false,
+    unanchored_start: false,
+    unanchored_end: false,
+};
+
+impl Syntax {
+    pub(crate) fn is_separator(&self, c: char) -> bool {
+        self.separators.contains(&c)
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// A single element of a compiled pattern.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) enum Token {
+    /// Matches exactly the characters in the given byte range of the literals
+    /// of the program.
+    Literal(u32, u32),
+    /// Matches exactly one arbitrary character.
+    Single,
+    /// Matches arbitrary many (including zero) characters.
+    Multi,
+    /// Matches one character of the character class with the given index.
+    Class(u32),
+    /// Matches one of the branches of the alternation with the given index.
+    Alt(u32),
+    /// Continues matching at the given token index. Ends every alternation branch.
+    Jump(u32),
+    /// Matches one path separator. Holds the separator used in the pattern.
+    Separator(char),
+    /// Matches zero or more whole path components, each followed by a
+    /// separator. Holds the separator used in the pattern after the `**`.
+    Components(char),
+    /// Matches arbitrary many characters including path separators.
+    GlobStar,
+    /// Matches arbitrary many characters of any kind, including path
+    /// separators and leading dots. Implied by an unanchored start or end.
+    Skip,
+}
+
+impl Token {
+    /// Returns true if the token matches input which is not fixed by the pattern.
+    pub(crate) fn is_wildcard(&self) -> bool {
+        matches!(
+            self,
+            Token::Single
+                | Token::Multi
+                | Token::Class(_)
+                | Token::Components(_)
+                | Token::GlobStar
+                | Token::Skip
+        )
+    }
+}
+
+/// Simple forms of a program which are matched by comparing the input with a
+/// single literal instead of walking the tokens. The literal is the only
+/// literal token of the program, see [`Program::shape_literal`].
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub(crate) enum Shape {
+    /// Matches only the literal.
+    #[default]
+    Exact,
+    /// Matches input starting with the literal.
+    Prefix,
+    /// Matches input ending with the literal.
+    Suffix,
+    /// Matches input containing the literal.
+    Contains,
+    /// Has to be matched token by token.
+    Tokens,
+}
+
+/// Branches of a `{a,b}` alternation within the token list.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub(crate) struct Alternation {
+    /// Index of the first token of each branch.
+    pub(crate) branches: Vec<usize>,
+    /// Index of the first token after the alternation.
+    pub(crate) end: usize,
+}
+
+/// A pattern compiled into a flat list of tokens.
+///
+/// The pattern string is stored at the start of the literals, and literal
+/// tokens point into it where they are written without escapes, so that plain
+/// patterns keep their text only once. Everything most patterns do not need
+/// is kept out of line.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
+pub(crate) struct Program {
+    pub(crate) tokens: Box<[Token]>,
+    /// The pattern string followed by the text of literal tokens which do
+    /// not occur in it.
+    pub(crate) literals: Box<str>,
+    /// Length of the pattern string at the start of the literals.
+    pattern_len: u32,
+    /// How case-sensitive matches can be decided by a single comparison.
+    pub(crate) shape: Shape,
+    /// Index of the longest literal token outside of alternations, whose text
+    /// every match contains. Only refers to a literal token if there is one
+    /// and its index fits.
+    required: u16,
+    extra: Option<Box<Extra>>,
+    /// Bit-parallel matcher if the pattern is short and only has literal
+    /// chars and wildcards.
+    pub(crate) shift_and: Option<Box<ShiftAnd>>,
+}
+
+/// Parts of a program which patterns without optional syntax do not need.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
+struct Extra {
+    syntax: Syntax,
+    /// Byte range of the folded text of each literal token within the
+    /// literals, by token index. Empty unless matched case-insensitively.
+    folded: Vec<(u32, u32)>,
+    /// Byte range within the folded literals of the longest run of ASCII
+    /// chars which every case-insensitive match contains, see
+    /// [`Program::required_literal_ignore_case`].
+    required_folded: (u32, u32),
+    classes: Vec<CharClass>,
+    alternations: Vec<Alternation>,
+}
+
+impl Program {
+    /// Returns the syntax the program was compiled with.
+    pub(crate) fn syntax(&self) -> &Syntax {
+        self.extra
+            .as_ref()
+            .map_or(&DEFAULT_SYNTAX, |extra| &extra.syntax)
+    }
+
+    /// Returns the range of the folded text of each literal token, by token
+    /// index. Empty unless the literals are folded.
+    pub(crate) fn folded(&self) -> &[(u32, u32)] {
+        self.extra.as_ref().map_or(&[], |extra| &extra.folded)
+    }
+
+    pub(crate) fn classes(&self) -> &[CharClass] {
+        self.extra.as_ref().map_or(&[], |extra| &extra.classes)
+    }
+
+    pub(crate) fn alternations(&self) -> &[Alternation] {
+        self.extra.as_ref().map_or(&[], |extra| &extra.alternations)
+    }
+
+    pub(crate) fn class(&self, idx: u32) -> &CharClass {
+        &self.classes()[idx as usize]
+    }
+
+    pub(crate) fn alternation(&self, idx: u32) -> &Alternation {
+        &self.alternations()[idx as usize]
+    }
+
+    fn extra_mut(&mut self) -> &mut Extra {
+        self.extra.get_or_insert_with(Default::default)
+    }
+
+    /// Returns the pattern string, see [`set_pattern`](Self::set_pattern).
+    pub(crate) fn pattern(&self) -> &str {
+        &self.literals[..self.pattern_len as usize]
+    }
+
+    /// Renders the program with the given wildcards and stores the result as
+    /// the pattern string. Literal tokens are moved into the pattern string
+    /// where possible. Folded literals are dropped.
+    pub(crate) fn set_pattern(&mut self, multi: char, single: char) {
+        let mut renderer = Renderer::new(self, multi, single, self.syntax());
+        renderer.render_range(self.pattern_tokens(self.syntax()), false);
+        let Renderer {
+            pattern: mut text,
+            spans,
+            ..
+        } = renderer;
+        let pattern_len = text.len();
+        let mut spans = spans.into_iter().peekable();
+        let mut tokens = core::mem::take(&mut self.tokens);
+        for (idx, token) in tokens.iter_mut().enumerate() {
+            if let Token::Literal(start, end) = *token {
+                *token = match spans.next_if(|&(span_idx, ..)| span_idx == idx) {
+                    Some((_, start, end)) => Token::Literal(start, end),
+                    None => {
+                        let literal_start = text.len() as u32;
+                        text.push_str(self.literal(start, end));
+                        Token::Literal(literal_start, text.len() as u32)
+                    }
+                };
+            }
+        }
+        self.tokens = tokens;
+        self.literals = text.into_boxed_str();
+        self.pattern_len = pattern_len as u32;
+        if let Some(extra) = &mut self.extra {
+            extra.folded = Vec::new();
+        }
+    }
+
+    /// Returns true if the program can be matched by only backtracking to the
+    /// last multi-wildcard.
+    pub(crate) fn is_linear(&self) -> bool {
+        self.alternations().is_empty()
+            && !self
+                .tokens
+                .iter()
+                .any(|token| matches!(token, Token::Components(_) | Token::GlobStar | Token::Skip))
+    }
+
+    /// Returns true if the program only consists of literal characters.
+    pub(crate) fn is_literal(&self) -> bool {
+        self.tokens
+            .iter()
+            .all(|token| matches!(token, Token::Literal(..)))
+    }
+
+    /// Returns the text of a literal token.
+    pub(crate) fn literal(&self, start: u32, end: u32) -> &str {
+        &self.literals[start as usize..end as usize]
+    }
+
+    /// Appends the folded text of every literal token to the literals, so
+    /// that case-insensitive matching does not have to fold the pattern again.
+    /// The folded text has one char per char of the literal, see [`fold`].
+    pub(crate) fn fold_case(&mut self) {
+        if !self.folded().is_empty() {
+            return;
+        }
+        let mut literals = String::from(core::mem::take(&mut self.literals));
+        let base = literals.len();
+        let mut text = String::new();
+        let folded = self
+            .tokens
+            .iter()
+            .map(|&token| match token {
+                Token::Literal(start, end) => {
+                    let folded_start = base + text.len();
+                    text.extend(literals[start as usize..end as usize].chars().map(fold));
+                    (folded_start as u32, (base + text.len()) as u32)
+                }
+                _ => (0, 0),
+            })
+            .collect();
+        literals.push_str(&text);
+        self.literals = literals.into_boxed_str();
+        self.extra_mut().folded = folded;
+        self.extra_mut().required_folded = self.longest_folded_run();
+        self.shift_and = ShiftAnd::new(self, true).map(Box::new);
+    }
+
+    /// Returns the literal of a program whose shape is not `Tokens`.
+    pub(crate) fn shape_literal(&self) -> &str {
+        self.tokens
+            .iter()
+            .find_map(|&token| self.literal_of(token))
+            .unwrap_or("")
+    }
+
+    /// Returns the literal characters every match starts with.
+    pub(crate) fn literal_prefix(&self) -> String {
+        self.tokens
+            .iter()
+            .map_while(|&token| self.literal_of(token))
+            .collect()
+    }
+
+    /// Returns the literal characters every match ends with.
+    pub(crate) fn literal_suffix(&self) -> String {
+        let mut suffix = self
+            .tokens
+            .iter()
+            .rev()
+            .map_while(|&token| self.literal_of(token))
+            .collect::<Vec<_>>();
+        suffix.reverse();
+        suffix.concat()
+    }
+
+    /// Returns the longest run of literal characters which occurs in every match.
+    pub(crate) fn required_literal(&self) -> &str {
+        match self.tokens.get(usize::from(self.required)) {
+            Some(&Token::Literal(start, end)) => self.literal(start, end),
+            _ => "",
+        }
+    }
+
+    /// Returns the longest run of literal characters which occurs in every
+    /// case-insensitive match in ASCII lowercase, so that inputs can be searched
+    /// for it ignoring ASCII case. Empty unless the literals are folded.
+    ///
+    /// Only ASCII chars are part of the run, and no `k`, which also equals the
+    /// non-ASCII Kelvin sign when ignoring case.
+    pub(crate) fn required_literal_ignore_case(&self) -> &str {
+        self.extra.as_ref().map_or("", |extra| {
+            let (start, end) = extra.required_folded;
+            self.literal(start, end)
+        })
+    }
+
+    /// Returns the indices of the literal tokens outside of alternations, which
+    /// occur in every match.
+    fn required_tokens(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut idx = 0;
+        core::iter::from_fn(move || loop {
+            let token = *self.tokens.get(idx)?;
+            let current = idx;
+            idx = match token {
+                // Literals within alternation branches are optional.
+                Token::Alt(alt) => self.alternation(alt).end,
+                _ => idx + 1,
+            };
+            if let Token::Literal(..) = token {
+                return Some(current);
+            }
+        })
+    }
+
+    /// Returns the index of the longest literal token outside of alternations,
+    /// or `u16::MAX` if there is none or its index does not fit.
+    fn longest_token(&self) -> u16 {
+        let mut longest = (usize::MAX, 0);
+        for idx in self.required_tokens() {
+            if let Token::Literal(start, end) = self.tokens[idx] {
+                if end - start > longest.1 {
+                    longest = (idx, end - start);
+                }
+            }
+        }
+        u16::try_from(longest.0).unwrap_or(u16::MAX)
+    }
+
+    /// Returns the byte range of the longest run of ASCII chars other than
+    /// `k` in the folded text of the literal tokens outside of alternations.
+    fn longest_folded_run(&self) -> (u32, u32) {
+        let mut longest = (0, 0);
+        for idx in self.required_tokens() {
+            let (start, end) = self.folded()[idx];
+            let mut run_start = start;
+            let text = self.literal(start, end);
+            let breaks = text
+                .char_indices()
+                .filter(|&(_, c)| !c.is_ascii() || c == 'k')
+                .map(|(pos, c)| (start + pos as u32, c.len_utf8() as u32))
+                .chain(core::iter::once((end, 0)));
+            for (run_end, len) in breaks {
+                if run_end - run_start > longest.1 - longest.0 {
+                    longest = (run_start, run_end);
+                }
+                run_start = run_end + len;
+            }
+        }
+        longest
+    }
+
+    /// Returns the index range of the tokens written in the pattern. Tokens
+    /// outside of it are implied by an unanchored start or end.
+    pub(crate) fn pattern_tokens(&self, syntax: &Syntax) -> core::ops::Range<usize> {
+        let start = usize::from(syntax.unanchored_start);
+        let end = self.tokens.len() - usize::from(syntax.unanchored_end);
+        start..end
+    }
+
+    fn literal_of(&self, token: Token) -> Option<&str> {
+        match token {
+            Token::Literal(start, end) => Some(self.literal(start, end)),
+            _ => None,
+        }
+    }
+}
+
+/// Maximum number of alternations which can be nested in each other. Deeper
+/// alternations are matched literally, so that compiling, rendering and
+/// translating a pattern needs bounded stack space.
+pub(crate) const MAX_DEPTH: usize = 64;
+
+/// Parser for patterns with the given wildcard characters and syntax.
+pub(crate) struct Parser<'a> {
+    pattern: &'a str,
+    chars: Chars<'a>,
+    multi: char,
+    single: char,
+    syntax: &'a Syntax,
+    tokens: Vec<Token>,
+    literals: String,
+    classes: Vec<CharClass>,
+    alternations: Vec<Alternation>,
+    /// Whether the next token starts a new path segment.
+    segment_start: bool,
+    /// Number of unclosed `{` which are nested too deeply and are thus
+    /// literals. Their `,` and `}` are literals as well.
+    literal_braces: usize,
+    /// First syntax error. Invalid syntax is parsed as literal characters.
+    error: Option<PatternError>,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(pattern: &'a str, multi: char, single: char, syntax: &'a Syntax) -> Self {
+        Parser {
+            pattern,
+            chars: pattern.chars(),
+            multi,
+            single,
+            syntax,
+            tokens: Vec::with_capacity(pattern.len()),
+            literals: String::with_capacity(pattern.len()),
+            classes: Vec::new(),
+            alternations: Vec::new(),
+            segment_start: true,
+            literal_braces: 0,
+            error: None,
+        }
+    }
+
+    /// Parses the pattern. Invalid syntax is matched literally.
+    pub(crate) fn parse(mut self) -> Program {
+        self.parse_sequence(0);
+        self.finish()
+    }
+
+    /// Parses the pattern and fails on the first invalid syntax.
+    pub(crate) fn try_parse(mut self) -> Result<Program, PatternError> {
+        self.parse_sequence(0);
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.finish()),
+        }
+    }
+
+    fn finish(mut self) -> Program {
+        self.unanchor();
+        let shape = self.shape();
+        let mut syntax = self.syntax.clone();
+        // The order of separators does not matter, so that equal patterns compare equal.
+        syntax.separators.sort_unstable();
+        syntax.separators.dedup();
+        let extra = Extra {
+            syntax,
+            folded: Vec::new(),
+            required_folded: (0, 0),
+            classes: self.classes,
+            alternations: self.alternations,
+        };
+        // Patterns are often kept for a long time, so they should not hold
+        // on to the capacity reserved for parsing.
+        let mut program = Program {
+            tokens: self.tokens.into_boxed_slice(),
+            literals: self.literals.into_boxed_str(),
+            pattern_len: 0,
+            shape,
+            required: u16::MAX,
+            extra: (extra != Extra::default()).then(|| Box::new(extra)),
+            shift_and: None,
+        };
+        program.required = program.longest_token();
+        program.shift_and = ShiftAnd::new(&program, false).map(Box::new);
+        program
+    }
+
+    /// Surrounds the tokens with wildcards for an unanchored start or end.
+    /// They match any input, so the wildcards of the pattern are only used if
+    /// they do so as well.
+    fn unanchor(&mut self) {
+        let wildcard = if self.syntax.literal_leading_dot {
+            Token::Skip
+        } else if self.syntax.separators.is_empty() {
+            Token::Multi
+        } else {
+            Token::GlobStar
+        };
+        if self.syntax.unanchored_start {
+            self.tokens.insert(0, wildcard);
+            for token in &mut self.tokens {
+                if let Token::Jump(target) = token {
+                    *target += 1;
+                }
+            }
+            for alternation in &mut self.alternations {
+                alternation
+                    .branches
+                    .iter_mut()
+                    .for_each(|branch| *branch += 1);
+                alternation.end += 1;
+            }
+        }
+        if self.syntax.unanchored_end {
+            self.tokens.push(wildcard);
+        }
+    }
+
+    /// Returns the shape of the tokens. Wildcards only fit a shape if they can
+    /// match any input, i.e. if there are neither separators nor hidden dots.
+    fn shape(&self) -> Shape {
+        let any = self.syntax.separators.is_empty() && !self.syntax.literal_leading_dot;
+        match *self.tokens.as_slice() {
+            [] | [Token::Literal(..)] => Shape::Exact,
+            [Token::Multi] | [Token::Literal(..), Token::Multi] if any => Shape::Prefix,
+            [Token::Multi, Token::Literal(..)] if any => Shape::Suffix,
+            [Token::Multi, Token::Literal(..), Token::Multi] if any => Shape::Contains,
+            _ => Shape::Tokens,
+        }
+    }
+
+    /// Returns the char offset of the given char which has just been consumed.
+    fn offset_of(&self, c: char) -> usize {
+        let end = self.pattern.len() - self.chars.as_str().len();
+        self.pattern[..end - c.len_utf8()].chars().count()
+    }
+
+    /// Appends the char to the literals and returns a token matching it.
+    fn literal(&mut self, c: char) -> Token {
+        let start = self.literals.len() as u32;
+        self.literals.push(c);
+        Token::Literal(start, self.literals.len() as u32)
+    }
+
+    /// Appends the token, merging consecutive literals into one token.
+    fn push(&mut self, token: Token) {
+        match (self.tokens.last_mut(), token) {
+            (Some(Token::Literal(_, end)), Token::Literal(start, next_end)) if *end == start => {
+                *end = next_end;
+            }
+            _ => self.tokens.push(token),
+        }
+    }
+
+    fn fail(&mut self, kind: PatternErrorKind, offset: usize) {
+        if self.error.is_none() {
+            self.error = Some(PatternError::new(kind, offset));
+        }
+    }
+
+    /// Parses tokens until the end of the pattern or, inside of an alternation,
+    /// until a `,` or `}` which is returned.
+    fn parse_sequence(&mut self, depth: usize) -> Option<char> {
+        while let Some(c) = self.chars.next() {
+            let token = if Some(c) == self.syntax.escape {
+                match self.chars.next() {
+                    Some(escaped) => self.literal(escaped),
+                    None => {
+                        let offset = self.offset_of(c);
+                        self.fail(PatternErrorKind::DanglingEscape, offset);
+                        self.literal(c)
+                    }
+                }
+            } else if c == self.multi {
+                match self.parse_globstar(depth) {
+                    Some(token) => token,
+                    // Consecutive multi-wildcards are simplified to a single one.
+                    None if self.tokens.last() == Some(&Token::Multi) => continue,
+                    None => Token::Multi,
+                }
+            } else if c == self.single {
+                Token::Single
+            } else if c == '[' && self.syntax.classes {
+                match CharClass::parse(&mut self.chars, self.syntax.escape) {
+                    Some(class) => {
+                        self.classes.push(class);
+                        Token::Class(self.classes.len() as u32 - 1)
+                    }
+                    None => {
+                        let offset = self.offset_of(c);
+                        self.fail(PatternErrorKind::UnterminatedClass, offset);
+                        self.literal(c)
+                    }
+                }
+            } else if c == '{' && self.syntax.braces {
+                let offset = self.offset_of(c);
+                if depth < MAX_DEPTH && self.literal_braces == 0 {
+                    self.parse_alternation(depth, offset);
+                    continue;
+                }
+                if self.literal_braces == 0 {
+                    let kind = PatternErrorKind::NestingTooDeep {
+                        max_depth: MAX_DEPTH,
+                    };
+                    self.fail(kind, offset);
+                }
+                self.literal_braces += 1;
+                self.literal(c)
+            } else if self.literal_braces > 0 && (c == ',' || c == '}') {
+                if c == '}' {
+                    self.literal_braces -= 1;
+                }
+                self.literal(c)
+            } else if depth > 0 && (c == ',' || c == '}') {
+                return Some(c);
+            } else if self.syntax.is_separator(c) {
+                Token::Separator(c)
+            } else {
+                self.literal(c)
+            };
+            self.segment_start = matches!(token, Token::Separator(_) | Token::Components(_));
+            self.push(token);
+        }
+        None
+    }
+
+    /// Parses the rest of a `**` path segment whose first multi-wildcard has
+    /// already been consumed. Returns `None` if the multi-wildcard is not part
+    /// of a `**` segment in path mode.
+    fn parse_globstar(&mut self, depth: usize) -> Option<Token> {
+        let mut rest = self.chars.clone();
+        let mut count = 1;
+        while rest.clone().next() == Some(self.multi) {
+            rest.next();
+            count += 1;
+        }
+        if self.syntax.separators.is_empty() || count < 2 || !self.segment_start {
+            return None;
+        }
+        let next = rest.clone().next();
+        let token = match next {
+            None => Token::GlobStar,
+            Some(c) if depth > 0 && (c == ',' || c == '}') => Token::GlobStar,
+            Some(c) if self.syntax.is_separator(c) => {
+                rest.next();
+                Token::Components(c)
+            }
+            Some(_) => return None,
+        };
+        self.chars = rest;
+        Some(token)
+    }
+
+    /// Parses an alternation whose opening `{` at the given char offset has
+    /// already been consumed.
+    ///
+    /// An unterminated alternation can only end at the end of the pattern. Its
+    /// tokens are kept and only the braces and separators are turned into
+    /// literals, so that the pattern does not need to be parsed again.
+    fn parse_alternation(&mut self, depth: usize, offset: usize) {
+        let start = self.tokens.len();
+        let segment_start = self.segment_start;
+        // Placeholder which is replaced once the alternation is complete.
+        self.tokens.push(Token::Jump(0));
+        let mut branches = Vec::new();
+        let mut separators = Vec::new();
+        loop {
+            branches.push(self.tokens.len());
+            self.segment_start = segment_start;
+            let terminator = self.parse_sequence(depth + 1);
+            if terminator.is_none() {
+                self.fail(PatternErrorKind::UnterminatedAlternation, offset);
+                self.tokens[start] = self.literal('{');
+                for idx in separators {
+                    self.tokens[idx] = self.literal(',');
+                }
+                return;
+            }
+            separators.push(self.tokens.len());
+            self.tokens.push(Token::Jump(0));
+            if terminator == Some('}') {
+                break;
+            }
+        }
+
+        let end = self.tokens.len();
+        for idx in separators {
+            self.tokens[idx] = Token::Jump(end as u32);
+        }
+        self.alternations.push(Alternation { branches, end });
+        self.tokens[start] = Token::Alt(self.alternations.len() as u32 - 1);
+        self.segment_start = false;
+    }
+}
+
+/// Writes the program back as a pattern. Literal characters which would
+/// otherwise have a special meaning are prefixed with the escape character.
+#[cfg(test)]
+fn render(program: &Program, multi: char, single: char, syntax: &Syntax) -> String {
+    let mut renderer = Renderer::new(program, multi, single, syntax);
+    renderer.render_range(program.pattern_tokens(syntax), false);
+    renderer.pattern
+}
+
+/// Writes tokens as a pattern.
+struct Renderer<'a> {
+    program: &'a Program,
+    multi: char,
+    single: char,
+    syntax: &'a Syntax,
+    pattern: String,
+    /// Token index and byte range in the pattern of every literal token
+    /// which is written without escapes.
+    spans: Vec<(usize, u32, u32)>,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(program: &'a Program, multi: char, single: char, syntax: &'a Syntax) -> Self {
+        Renderer {
+            program,
+            multi,
+            single,
+            syntax,
+            pattern: String::with_capacity(program.literals.len() + program.tokens.len()),
+            spans: Vec::new(),
+        }
+    }
+
+    fn render_range(&mut self, range: core::ops::Range<usize>, in_braces: bool) {
+        let program = self.program;
+        let (multi, single) = (self.multi, self.single);
+        let mut idx = range.start;
+        while idx < range.end {
+            match program.tokens[idx] {
+                Token::Multi => self.pattern.push(multi),
+                Token::Single => self.pattern.push(single),
+                Token::Class(class) => self.pattern.push_str(program.class(class).source()),
+                Token::Alt(alt) => {
+                    let alternation = program.alternation(alt);
+                    self.pattern.push('{');
+                    for (branch, &start) in alternation.branches.iter().enumerate() {
+                        if branch > 0 {
+                            self.pattern.push(',');
+                        }
+                        let end = alternation
+                            .branches
+                            .get(branch + 1)
+                            .copied()
+                            .unwrap_or(alternation.end);
+                        // Every branch ends with a jump which is not rendered.
+                        self.render_range(start..end - 1, true);
+                    }
+                    self.pattern.push('}');
+                    idx = alternation.end;
+                    continue;
+                }
+                // Implied tokens are not written in the pattern.
+                Token::Jump(_) | Token::Skip => {}
+                Token::Separator(c) => self.pattern.push(c),
+                Token::Components(c) => self.pattern.extend([multi, multi, c].iter()),
+                Token::GlobStar => self.pattern.extend([multi, multi].iter()),
+                Token::Literal(start, end) => {
+                    let rendered_start = self.pattern.len();
+                    self.render_literal(program.literal(start, end), in_braces);
+                    if self.pattern.len() - rendered_start == (end - start) as usize {
+                        let span = (idx, rendered_start as u32, self.pattern.len() as u32);
+                        self.spans.push(span);
+                    }
+                }
+            }
+            idx += 1;
+        }
+    }
+
+    fn render_literal(&mut self, literal: &str, in_braces: bool) {
+        let syntax = self.syntax;
+        let escape = match syntax.escape {
+            Some(escape) => escape,
+            None => return self.pattern.push_str(literal),
+        };
+        for c in literal.chars() {
+            if c == escape
+                || c == self.multi
+                || c == self.single
+                || (c == '[' && syntax.classes)
+                || (c == '{' && syntax.braces)
+                || ((c == ',' || c == '}') && in_braces)
+                || syntax.is_separator(c)
+            {
+                self.pattern.push(escape);
+            }
+            self.pattern.push(c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ntest::test_case;
+
+    const ALL: Syntax = Syntax {
+        escape: Some('\\'),
+        classes: true,
+        braces: true,
+        separators: Vec::new(),
+        literal_leading_dot: false,
+        unanchored_start: false,
+        unanchored_end: false,
+    };
+
+    fn parse(pattern: &str, syntax: Syntax) -> Program {
+        Parser::new(pattern, '*', '?', &syntax).parse()
+    }
+
+    #[test]
+    fn alternation_tokens() {
+        let program = parse("a{b,}c", ALL);
+        assert_eq!(
+            vec![
+                Token::Literal(0, 1),
+                Token::Alt(0),
+                Token::Literal(1, 2),
+                Token::Jump(5),
+                Token::Jump(5),
+                Token::Literal(2, 3),
+            ],
+            program.tokens.to_vec()
+        );
+        assert_eq!(
+            vec![Alternation {
+                branches: vec![2, 4],
+                end: 5
+            }],
+            program.alternations()
+        );
+    }
+
+    #[test]
+    fn unanchored_tokens() {
+        let syntax = Syntax {
+            unanchored_start: true,
+            unanchored_end: true,
+            ..ALL
+        };
+        let program = parse("{a,b}", syntax.clone());
+        assert_eq!(
+            vec![
+                Token::Multi,
+                Token::Alt(0),
+                Token::Literal(0, 1),
+                Token::Jump(6),
+                Token::Literal(1, 2),
+                Token::Jump(6),
+                Token::Multi,
+            ],
+            program.tokens.to_vec()
+        );
+        assert_eq!(vec![2, 4], program.alternations()[0].branches);
+        assert_eq!(6, program.alternations()[0].end);
+        assert_eq!(1..6, program.pattern_tokens(&syntax));
+        assert_eq!("{a,b}", render(&program, '*', '?', &syntax));
+    }
+
+    #[test]
+    fn unterminated_alternation_is_literal() {
+        let program = parse("{a,{b}", ALL);
+        assert_eq!(Token::Literal(2, 3), program.tokens[0]);
+        assert_eq!(Token::Literal(3, 4), program.tokens[2]);
+        assert_eq!(Token::Alt(0), program.tokens[3]);
+        assert_eq!("ab{,", &*program.literals);
+    }
+
+    #[test]
+    fn nested_too_deep_is_literal() {
+        let pattern = "{".repeat(65) + "a,b}" + &"}".repeat(64);
+        let program = parse(&pattern, ALL);
+        assert_eq!(64, program.alternations().len());
+        assert_eq!("{a,b}", &*program.literals);
+        assert_eq!(pattern, render(&program, '*', '?', &ALL).replace('\\', ""));
+
+        let error = Parser::new(&pattern, '*', '?', &ALL)
+            .try_parse()
+            .unwrap_err();
+        assert_eq!(
+            PatternErrorKind::NestingTooDeep { max_depth: 64 },
+            error.kind()
+        );
+        assert_eq!(64, error.offset());
+    }
+
+    #[test]
+    fn literal_runs() {
+        let mut program = parse(r"ab*c\*d?ä", ALL);
+        assert_eq!(
+            vec![
+                Token::Literal(0, 2),
+                Token::Multi,
+                Token::Literal(2, 5),
+                Token::Single,
+                Token::Literal(5, 7),
+            ],
+            program.tokens.to_vec()
+        );
+        assert_eq!("c*d", program.literal(2, 5));
+        assert_eq!("ab", program.literal_prefix());
+        assert_eq!("ä", program.literal_suffix());
+        assert_eq!("c*d", program.required_literal());
+        program.fold_case();
+        assert_eq!("c*d", program.required_literal_ignore_case());
+    }
+
+    #[test_case("*Ab*CdE?", "cde")]
+    #[test_case("*xÄyzK", "yz")]
+    #[test_case("*ÄÖ", "")]
+    fn required_literal_ignore_case(pattern: &str, expected: &str) {
+        let mut program = parse(pattern, ALL);
+        program.fold_case();
+        assert_eq!(expected, program.required_literal_ignore_case());
+    }
+
+    #[test_case("", false, "Exact")]
+    #[test_case(r"a\*b", false, "Exact")]
+    #[test_case("*", false, "Prefix")]
+    #[test_case("ab*", false, "Prefix")]
+    #[test_case("**ab", false, "Suffix")]
+    #[test_case("*ab*", false, "Contains")]
+    #[test_case("*a?", false, "Tokens")]
+    #[test_case("a*b", false, "Tokens")]
+    #[test_case("ab", true, "Exact")]
+    #[test_case("ab*", true, "Tokens")]
+    fn shapes(pattern: &str, path: bool, expected: &str) {
+        let separators = if path { vec!['/'] } else { Vec::new() };
+        let program = parse(pattern, Syntax { separators, ..ALL });
+        assert_eq!(expected, format!("{:?}", program.shape));
+    }
+
+    #[test]
+    fn pattern_shares_literals() {
+        let mut program = parse("*.rs", Syntax::default());
+        program.set_pattern('*', '?');
+        assert_eq!("*.rs", program.pattern());
+        assert_eq!("*.rs", &*program.literals);
+        assert_eq!(".rs", program.shape_literal());
+
+        // Escaped literals are kept after the pattern.
+        let mut program = parse(r"a\*b*{c,d}", ALL);
+        program.set_pattern('*', '?');
+        assert_eq!(r"a\*b*{c,d}", program.pattern());
+        assert_eq!(r"a\*b*{c,d}a*b", &*program.literals);
+        assert_eq!("a*b", program.literal_prefix());
+        assert_eq!(Token::Literal(6, 7), program.tokens[3]);
+    }
+
+    #[test_case("[ab", "UnterminatedClass", 0)]
+    #[test_case("ä*[!a", "UnterminatedClass", 2)]
+    #[test_case("{a,b", "UnterminatedAlternation", 0)]
+    #[test_case("{a,{b}", "UnterminatedAlternation", 0)]
+    #[test_case("a{b,{c,d}", "UnterminatedAlternation", 1)]
+    #[test_case(r"ab\", "DanglingEscape", 2)]
+    #[test_case(r"{a,b\", "DanglingEscape", 4)]
+    #[test_case(r"[a\", "UnterminatedClass", 0)]
+    fn try_parse_error(pattern: &str, kind: &str, offset: usize) {
+        let error = Parser::new(pattern, '*', '?', &ALL)
+            .try_parse()
+            .unwrap_err();
+        assert_eq!(kind, format!("{:?}", error.kind()));
+        assert_eq!(offset, error.offset());
+    }
+
+    #[test_case(r"[]]{a,\}}\[")]
+    #[test_case("")]
+    fn try_parse_valid(pattern: &str) {
+        let program = Parser::new(pattern, '*', '?', &ALL).try_parse();
+        assert_eq!(Ok(parse(pattern, ALL)), program);
+    }
+
+    #[test_case(r"{\,,\}}")]
+    #[test_case(r"\{a,b}")]
+    #[test_case(r"{[{}],\[}")]
+    #[test_case(r"a,b}")]
+    #[test_case(r"*\*{\*,?}")]
+    fn render_roundtrip(pattern: &str) {
+        let program = parse(pattern, ALL);
+        let rendered = render(&program, '*', '?', &ALL);
+        assert_eq!(pattern, rendered);
+    }
+
+    #[test]
+    fn render_escapes_only_special_chars() {
+        let program = parse(r"\a{\,}\,", ALL);
+        let rendered = render(&program, '*', '?', &ALL);
+        assert_eq!(r"a{\,},", rendered);
+    }
+}
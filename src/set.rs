@@ -0,0 +1,341 @@
+//! Matching one input against many patterns at once.
+
+use crate::engine::contains_ignore_ascii_case;
+use crate::WildMatchPattern;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+
+/// A set of wildcard patterns using `*` as the multi-character wildcard and
+/// `?` as the single-character wildcard.
+pub type WildMatchSet = WildMatchPatternSet<'*', '?'>;
+
+/// A set of wildcard patterns which are matched against an input together.
+///
+/// The patterns are indexed by their literal parts when the set is built, so
+/// that only the patterns which can possibly match an input need to be tested:
+///
+/// - Patterns without any wildcard are looked up by their text.
+/// - Patterns starting with literal characters are stored in a prefix tree, so
+///   that common prefixes are compared once for all patterns sharing them.
+/// - Patterns ending with literal characters, like `*.rs`, are stored in a
+///   tree of their reversed suffixes.
+/// - All remaining patterns are only tested if the input contains their
+///   longest literal part.
+///
+/// Case-insensitive patterns are only tested if the input contains their
+/// longest literal part of ASCII characters when ignoring ASCII case.
+///
+/// Looking up an input in the trees costs time proportional to its length,
+/// however many patterns the set has. The remaining patterns each cost a
+/// search of the input for their literal part, and patterns without any
+/// literal characters, like `?*?`, are always tested.
+///
+/// ```
+/// # use wildmatch::{WildMatch, WildMatchSet};
+/// let set = WildMatchSet::new(vec![
+///     WildMatch::new("*.rs"),
+///     WildMatch::new("src/*"),
+///     WildMatch::new("Cargo.toml"),
+/// ]);
+/// assert_eq!(vec![0, 1], set.matches("src/lib.rs"));
+/// assert_eq!(Some(2), set.first_match("Cargo.toml"));
+/// assert!(!set.is_match("README.md"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WildMatchPatternSet<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> {
+    patterns: Vec<WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>>,
+    /// Indices of patterns without wildcards by their text.
+    literals: BTreeMap<String, Vec<usize>>,
+    /// Indices of patterns by their literal prefix.
+    prefixes: Trie,
+    /// Indices of patterns by their reversed literal suffix.
+    suffixes: Trie,
+    /// Indices of all other patterns, which are tested if the input contains
+    /// their required literal.
+    others: Vec<usize>,
+    /// Indices of case-insensitive patterns, which are tested if the input
+    /// contains their ASCII lowercase required literal when ignoring ASCII
+    /// case.
+    others_ignore_case: Vec<usize>,
+}
+
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char>
+    WildMatchPatternSet<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+    /// Constructor with the patterns of the set. The patterns are referred to by
+    /// their index in the given order.
+    pub fn new<I>(patterns: I) -> WildMatchPatternSet<MULTI_WILDCARD, SINGLE_WILDCARD>
+    where
+        I: IntoIterator<Item = WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>>,
+    {
+        let mut set = Self {
+            patterns: patterns.into_iter().collect(),
+            ..Self::default()
+        };
+        for (idx, pattern) in set.patterns.iter().enumerate() {
+            let program = &pattern.program;
+            if pattern.case_insensitive {
+                set.others_ignore_case.push(idx);
+                continue;
+            }
+            if program.is_literal() {
+                let literal = program.literal_prefix();
+                set.literals.entry(literal).or_default().push(idx);
+                continue;
+            }
+            let prefix = program.literal_prefix();
+            let suffix = program.literal_suffix();
+            if !prefix.is_empty() && prefix.len() >= suffix.len() {
+                set.prefixes.insert(prefix.chars(), idx);
+            } else if !suffix.is_empty() {
+                set.suffixes.insert(suffix.chars().rev(), idx);
+            } else {
+                set.others.push(idx);
+            }
+        }
+        set
+    }
+
+    /// Returns true if any pattern of the set matches the input.
+    pub fn is_match(&self, input: &str) -> bool {
+        self.first_match(input).is_some()
+    }
+
+    /// Returns the indices of all patterns matching the input in ascending order.
+    pub fn matches(&self, input: &str) -> Vec<usize> {
+        self.candidates(input)
+            .into_iter()
+            .filter(|&idx| self.patterns[idx].matches(input))
+            .collect()
+    }
+
+    /// Returns the lowest index of the patterns matching the input.
+    pub fn first_match(&self, input: &str) -> Option<usize> {
+        self.candidates(input)
+            .into_iter()
+            .find(|&idx| self.patterns[idx].matches(input))
+    }
+
+    /// Returns the patterns of the set.
+    pub fn patterns(&self) -> &[WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>] {
+        &self.patterns
+    }
+
+    /// Returns the number of patterns in the set.
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Returns true if the set contains no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns the indices of the patterns which can match the input in
+    /// ascending order.
+    fn candidates(&self, input: &str) -> Vec<usize> {
+        let mut candidates = Vec::new();
+        if let Some(literals) = self.literals.get(input) {
+            candidates.extend_from_slice(literals);
+        }
+        self.prefixes.collect(input.chars(), &mut candidates);
+        self.suffixes.collect(input.chars().rev(), &mut candidates);
+        candidates.extend(
+            self.others
+                .iter()
+                .filter(|&&idx| input.contains(self.patterns[idx].program.required_literal())),
+        );
+        candidates.extend(self.others_ignore_case.iter().filter(|&&idx| {
+            let literal = self.patterns[idx].program.required_literal_ignore_case();
+            contains_ignore_ascii_case(input.as_bytes(), literal.as_bytes())
+        }));
+        candidates.sort_unstable();
+        candidates
+    }
+}
+
+impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char>
+    FromIterator<WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>>
+    for WildMatchPatternSet<MULTI_WILDCARD, SINGLE_WILDCARD>
+{
+    fn from_iter<I>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = WildMatchPattern<MULTI_WILDCARD, SINGLE_WILDCARD>>,
+    {
+        Self::new(patterns)
+    }
+}
+
+/// Prefix tree mapping char sequences to the indices of patterns.
+#[derive(Debug, Clone)]
+struct Trie {
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    children: BTreeMap<char, usize>,
+    /// Indices of the patterns whose key ends at this node.
+    patterns: Vec<usize>,
+}
+
+impl Default for Trie {
+    fn default() -> Self {
+        Trie {
+            nodes: vec![TrieNode::default()],
+        }
+    }
+}
+
+impl Trie {
+    fn insert(&mut self, key: impl Iterator<Item = char>, pattern: usize) {
+        let mut node = 0;
+        for c in key {
+            node = match self.nodes[node].children.get(&c) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(c, child);
+                    child
+                }
+            };
+        }
+        self.nodes[node].patterns.push(pattern);
+    }
+
+    /// Adds the patterns of all keys which are a prefix of `input`.
+    fn collect(&self, input: impl Iterator<Item = char>, patterns: &mut Vec<usize>) {
+        let mut node = 0;
+        patterns.extend_from_slice(&self.nodes[node].patterns);
+        for c in input {
+            node = match self.nodes[node].children.get(&c) {
+                Some(&child) => child,
+                None => return,
+            };
+            patterns.extend_from_slice(&self.nodes[node].patterns);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WildMatch;
+    use ntest::test_case;
+
+    fn set(patterns: &[&str]) -> WildMatchSet {
+        patterns.iter().map(|p| WildMatch::new(p)).collect()
+    }
+
+    const PATTERNS: &[&str] = &[
+        "*.rs",
+        "src/*",
+        "Cargo.toml",
+        "src/lib.rs",
+        "*",
+        "*test*",
+        "?a?",
+        "src/*/mod.rs",
+        "",
+    ];
+
+    #[test_case("src/lib.rs")]
+    #[test_case("src/a/mod.rs")]
+    #[test_case("Cargo.toml")]
+    #[test_case("tests/test.rs")]
+    #[test_case("cat")]
+    #[test_case("")]
+    fn matches_like_each_pattern(input: &str) {
+        let set = set(PATTERNS);
+        let expected = PATTERNS
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| WildMatch::new(p).matches(input))
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        assert_eq!(expected, set.matches(input));
+        assert_eq!(expected.first().copied(), set.first_match(input));
+        assert_eq!(!expected.is_empty(), set.is_match(input));
+    }
+
+    #[test]
+    fn no_match() {
+        let set = set(&["*.rs", "src/*", "Cargo.toml", "?a?"]);
+        assert!(set.matches("README.md").is_empty());
+        assert_eq!(None, set.first_match("README.md"));
+        assert!(!set.is_match("README.md"));
+    }
+
+    #[test]
+    fn empty_set() {
+        let set = WildMatchSet::default();
+        assert!(set.is_empty());
+        assert_eq!(0, set.len());
+        assert!(!set.is_match(""));
+    }
+
+    #[test]
+    fn duplicate_patterns() {
+        let set = set(&["a*", "a*", "abc", "abc", "*c", "*c"]);
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], set.matches("abc"));
+        assert_eq!(6, set.len());
+    }
+
+    #[test]
+    fn case_insensitive_patterns() {
+        let set = WildMatchSet::new(vec![
+            WildMatch::new_case_insensitive("*.RS"),
+            WildMatch::new("*.RS"),
+            WildMatch::new_case_insensitive("CARGO.TOML"),
+        ]);
+        assert_eq!(vec![0], set.matches("lib.rs"));
+        assert_eq!(vec![2], set.matches("Cargo.toml"));
+        assert_eq!(vec![0, 1], set.matches("LIB.RS"));
+        assert!(set.matches("Cargo.lock").is_empty());
+    }
+
+    #[test]
+    fn case_insensitive_literals() {
+        let set = WildMatchSet::new(vec![
+            WildMatch::new_case_insensitive("*.MD"),
+            WildMatch::new_case_insensitive("*Ä?"),
+            WildMatch::new_case_insensitive("k*"),
+        ]);
+        let literals = set
+            .patterns
+            .iter()
+            .map(|m| m.program.required_literal_ignore_case())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![".md", "", ""], literals);
+        assert_eq!(vec![0], set.matches("README.Md"));
+        assert_eq!(vec![1], set.matches("xäy"));
+        assert_eq!(vec![2], set.matches("\u{212a}elvin"));
+    }
+
+    #[test]
+    fn patterns_with_syntax() {
+        let set = WildMatchSet::new(vec![
+            WildMatch::new_with_braces("{src,tests}/*.rs"),
+            WildMatch::new_with_braces("*.{rs,toml}"),
+            WildMatch::new_with_classes("[st]*"),
+            WildMatch::new_with_separators("src/**", &['/']),
+        ]);
+        assert_eq!(vec![0, 1, 2, 3], set.matches("src/lib.rs"));
+        assert_eq!(vec![0, 1, 2], set.matches("tests/a.rs"));
+        assert_eq!(vec![1], set.matches("Cargo.toml"));
+    }
+
+    #[test]
+    fn custom_wildcards() {
+        let set = WildMatchPatternSet::<'%', '_'>::new(vec![
+            WildMatchPattern::<'%', '_'>::new("%.rs"),
+            WildMatchPattern::<'%', '_'>::new("_at"),
+        ]);
+        assert_eq!(vec![0], set.matches("lib.rs"));
+        assert_eq!(vec![1], set.matches("cat"));
+    }
+}
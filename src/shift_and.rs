@@ -0,0 +1,172 @@
+//! Bit-parallel matching of short patterns.
+
+use crate::engine::fold;
+use crate::parse::{Program, Token};
+use alloc::vec::Vec;
+
+/// A pattern of literal chars and wildcards which fits into a machine word,
+/// matched with the shift-and algorithm.
+///
+/// Bit `i` of the state is set if the first `i` elements of the pattern can
+/// match the input read so far, so all ways a pattern can match are tracked at
+/// once and every input char is read only once.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub(crate) struct ShiftAnd {
+    /// Elements which match each folded literal char of the pattern, sorted
+    /// by char.
+    chars: Vec<(char, u64)>,
+    /// Elements which match any char.
+    singles: u64,
+    /// Multi-wildcards, which match any chars and may be skipped.
+    stars: u64,
+    /// Number of elements, which is also the bit of the accepting state.
+    len: u32,
+    /// Whether the matcher is for case-insensitive matching. Only
+    /// case-insensitive matchers have literal chars, which are folded.
+    case_insensitive: bool,
+}
+
+impl ShiftAnd {
+    /// Returns the matcher for the program if it consists of literal chars and
+    /// wildcards and is short enough. Case-sensitive programs with literals
+    /// are left to the engine, which is faster at searching the input for the
+    /// literal after a multi-wildcard.
+    pub(crate) fn new(program: &Program, case_insensitive: bool) -> Option<Self> {
+        let syntax = program.syntax();
+        if !syntax.separators.is_empty() || syntax.literal_leading_dot {
+            return None;
+        }
+        let mut chars: Vec<(char, u64)> = Vec::new();
+        let mut singles = 0;
+        let mut stars = 0;
+        let mut len = 0;
+        for (idx, &token) in program.tokens.iter().enumerate() {
+            match token {
+                Token::Literal(..) if !case_insensitive => return None,
+                Token::Literal(..) => {
+                    let (start, end) = program.folded()[idx];
+                    for c in program.literal(start, end).chars() {
+                        let bit = 1u64.checked_shl(len)?;
+                        match chars.iter_mut().find(|(other, _)| *other == c) {
+                            Some((_, elements)) => *elements |= bit,
+                            None => chars.push((c, bit)),
+                        }
+                        len += 1;
+                    }
+                }
+                Token::Single => singles |= 1u64.checked_shl(len)?,
+                Token::Multi => stars |= 1u64.checked_shl(len)?,
+                _ => return None,
+            }
+            if !matches!(token, Token::Literal(..)) {
+                len += 1;
+            }
+        }
+        // The accepting state needs a bit as well.
+        if len >= u64::BITS {
+            return None;
+        }
+        chars
+            .iter_mut()
+            .for_each(|(_, elements)| *elements |= singles);
+        chars.sort_unstable_by_key(|&(c, _)| c);
+        Some(ShiftAnd {
+            chars,
+            singles,
+            stars,
+            len,
+            case_insensitive,
+        })
+    }
+
+    /// Returns true if the pattern matches the entire input. Returns `None` if
+    /// the matcher was built for a different case sensitivity, so the input
+    /// has to be matched by another engine.
+    pub(crate) fn matches(&self, input: &str, case_insensitive: bool) -> Option<bool> {
+        if case_insensitive != self.case_insensitive {
+            return None;
+        }
+        let mut state = self.closure(1);
+        for c in input.chars() {
+            let c = if self.case_insensitive { fold(c) } else { c };
+            let elements = match self.chars.binary_search_by_key(&c, |&(c, _)| c) {
+                Ok(idx) => self.chars[idx].1,
+                Err(_) => self.singles,
+            };
+            state = self.closure(((state & elements) << 1) | (state & self.stars));
+            if state == 0 {
+                return Some(false);
+            }
+        }
+        Some(state & 1 << self.len != 0)
+    }
+
+    /// Adds the states reached by skipping multi-wildcards.
+    fn closure(&self, state: u64) -> u64 {
+        // Adding the multi-wildcards in the state to all multi-wildcards
+        // carries through each run of multi-wildcards from the first one in
+        // the state up to the element after the run.
+        let skipped = (state & self.stars).wrapping_add(self.stars) ^ self.stars;
+        state | skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::{Parser, Syntax};
+    use ntest::test_case;
+
+    fn shift_and(pattern: &str) -> Option<ShiftAnd> {
+        let mut program = Parser::new(pattern, '*', '?', &Syntax::default()).parse();
+        program.fold_case();
+        ShiftAnd::new(&program, true)
+    }
+
+    #[test_case("*a*b*c", "xaxbxc", true)]
+    #[test_case("*a*b*c", "xaxbxcx", false)]
+    #[test_case("a?c*", "abcde", true)]
+    #[test_case("a?c*", "ac", false)]
+    #[test_case("?ä?", "äää", true)]
+    #[test_case("", "", true)]
+    #[test_case("", "a", false)]
+    fn matches(pattern: &str, input: &str, expected: bool) {
+        let m = shift_and(pattern).unwrap();
+        assert_eq!(Some(expected), m.matches(input, true));
+    }
+
+    #[test_case("*?*", true)]
+    #[test_case("*a?", false)]
+    fn case_sensitive_without_literals(pattern: &str, built: bool) {
+        let program = Parser::new(pattern, '*', '?', &Syntax::default()).parse();
+        assert_eq!(built, ShiftAnd::new(&program, false).is_some());
+    }
+
+    #[test]
+    fn matches_case_insensitive() {
+        let mut program = Parser::new("*ä?K", '*', '?', &Syntax::default()).parse();
+        program.fold_case();
+        let m = ShiftAnd::new(&program, true).unwrap();
+        assert_eq!(Some(true), m.matches("xÄxk", true));
+        assert_eq!(Some(false), m.matches("xÄİ", true));
+        assert_eq!(None, m.matches("xÄxk", false));
+    }
+
+    #[test]
+    fn closure_skips_runs_of_multi_wildcards() {
+        let m = ShiftAnd {
+            stars: 0b0110_1100,
+            ..shift_and("").unwrap()
+        };
+        assert_eq!(0b1000_0001, m.closure(0b1000_0001));
+        assert_eq!(0b0001_1100, m.closure(0b0000_0100));
+        assert_eq!(0b1111_1100, m.closure(0b0010_1100));
+    }
+
+    #[test_case(63, true)]
+    #[test_case(64, false)]
+    fn max_len(len: usize, fits: bool) {
+        let pattern = "a?*".repeat(len / 3) + &"b".repeat(len % 3);
+        assert_eq!(fits, shift_and(&pattern).is_some());
+    }
+}
@@ -0,0 +1,806 @@
+//! Translation of compiled patterns into other pattern languages.
+
+use crate::parse::{Program, Syntax, Token};
+use crate::TranslateError;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+/// Characters with a special meaning in regular expressions.
+const REGEX_META: &[char] = &[
+    '\\', '.', '+', '*', '?', '(', ')', '|', '[', ']', '{', '}', '^', '$', '#', '&', '-', '~',
+];
+
+/// Regex which does not match anything, for patterns which cannot match any
+/// input. `^` cannot match after a character was consumed.
+const NOTHING: &str = ".^";
+
+/// Input position within a path segment.
+const INSIDE: usize = 0;
+/// Input position at the start of the input or of a path segment, where a
+/// leading dot can only be matched literally.
+const START: usize = 1;
+
+/// Returns an anchored regular expression which matches the same inputs as
+/// the program.
+pub(crate) fn to_regex(program: &Program, syntax: &Syntax, case_insensitive: bool) -> String {
+    let writer = RegexWriter { program, syntax };
+    let start = if syntax.literal_leading_dot {
+        START
+    } else {
+        INSIDE
+    };
+    let (common, tails) = writer.sequence(0..program.tokens.len(), start);
+    let body = if tails.iter().all(Option::is_none) {
+        String::from(NOTHING)
+    } else {
+        format!(
+            "{}{}",
+            common,
+            any_of(tails.iter().flatten().cloned().collect())
+        )
+    };
+
+    let mut flags = String::new();
+    if case_insensitive {
+        flags.push('i');
+    }
+    if program.tokens.iter().any(Token::is_wildcard) {
+        // Wildcards also match line breaks.
+        flags.push('s');
+    }
+    if flags.is_empty() {
+        format!("^{}$", body)
+    } else {
+        format!("(?{})^{}$", flags, body)
+    }
+}
+
+/// Writes tokens as regular expressions.
+///
+/// If leading dots have to match literally, whether a wildcard may match a
+/// `.` depends on the input before it. Since this cannot be expressed without
+/// look-around, the regex is built like an automaton over the `INSIDE` and
+/// `START` positions: every part is written once for each position it can
+/// start at and grouped by the position it ends at.
+struct RegexWriter<'a> {
+    program: &'a Program,
+    syntax: &'a Syntax,
+}
+
+impl RegexWriter<'_> {
+    /// Returns the regexes for the tokens in `range` starting at the given
+    /// position as a common prefix and the rest indexed by the position they
+    /// end at.
+    fn sequence(&self, range: Range<usize>, start: usize) -> (String, [Option<String>; 2]) {
+        // Regex all paths share, followed by the regexes for each position.
+        let mut common = String::new();
+        let mut tails: [Option<String>; 2] = [None, None];
+        tails[start] = Some(String::new());
+
+        let mut idx = range.start;
+        while idx < range.end {
+            // Tails continued with the same regex by end position.
+            let mut next: [Vec<(String, Vec<String>)>; 2] = [Vec::new(), Vec::new()];
+            for position in [INSIDE, START].iter().copied() {
+                if let Some(tail) = &tails[position] {
+                    for (regex, end) in self.token(idx, position) {
+                        match next[end].iter_mut().find(|(other, _)| *other == regex) {
+                            Some((_, tails)) => tails.push(tail.clone()),
+                            None => next[end].push((regex, vec![tail.clone()])),
+                        }
+                    }
+                }
+            }
+            tails = next.map(|continued| {
+                let regexes = continued
+                    .into_iter()
+                    .map(|(regex, tails)| format!("{}{}", any_of(tails), regex))
+                    .collect::<Vec<_>>();
+                non_empty(regexes)
+            });
+            if let [Some(tail), None] | [None, Some(tail)] = &mut tails {
+                // All paths end at the same position again.
+                common.push_str(tail);
+                tail.clear();
+            }
+            idx = match self.program.tokens[idx] {
+                Token::Alt(alt) => self.program.alternation(alt).end,
+                _ => idx + 1,
+            };
+        }
+        (common, tails)
+    }
+
+    /// Returns the regexes for the token at `idx` starting at the given
+    /// position together with the position they end at.
+    fn token(&self, idx: usize, position: usize) -> Vec<(String, usize)> {
+        let dot = self.syntax.literal_leading_dot;
+        let separator_end = if dot { START } else { INSIDE };
+        match self.program.tokens[idx] {
+            Token::Literal(start, end) => {
+                let literal = self.program.literal(start, end);
+                let regex = literal.chars().map(escape_regex).collect();
+                match literal.chars().last() {
+                    Some(c) if self.syntax.is_separator(c) => vec![(regex, separator_end)],
+                    _ => vec![(regex, INSIDE)],
+                }
+            }
+            Token::Separator(_) => vec![(self.separator(), separator_end)],
+            Token::Single => vec![(self.any(position == START), INSIDE)],
+            Token::Class(class) => match self.class(class, position == START) {
+                Some(regex) => vec![(regex, INSIDE)],
+                None => Vec::new(),
+            },
+            Token::Multi if position == START => {
+                vec![(String::new(), START), (self.nonempty(), INSIDE)]
+            }
+            Token::Multi => vec![(format!("{}*", self.any(false)), INSIDE)],
+            Token::GlobStar if !dot => vec![(String::from(".*"), INSIDE)],
+            Token::Skip => {
+                // Ends at the start of a segment after a separator, and
+                // within a segment after any other char.
+                let mut regexes = vec![
+                    (String::new(), position),
+                    (format!(".*{}", self.any(false)), INSIDE),
+                ];
+                if !self.syntax.separators.is_empty() {
+                    regexes.push((format!(".*{}", self.separator()), separator_end));
+                }
+                regexes
+            }
+            Token::Components(_) if !dot => {
+                vec![(format!("(?:.*{})?", self.separator()), INSIDE)]
+            }
+            token @ Token::GlobStar | token @ Token::Components(_) => {
+                // Whole components which do not start with a dot.
+                let components = format!("(?:(?:{})?{})*", self.nonempty(), self.separator());
+                let globstar = token == Token::GlobStar;
+                if position == START {
+                    let mut regexes = vec![(components.clone(), START)];
+                    if globstar {
+                        regexes.push((format!("{}{}", components, self.nonempty()), INSIDE));
+                    }
+                    regexes
+                } else {
+                    // The rest of the current segment comes first.
+                    let rest = format!("{}*", self.any(false));
+                    let mut regexes =
+                        vec![(format!("{}{}{}", rest, self.separator(), components), START)];
+                    if globstar {
+                        let end = format!(
+                            "{}(?:{}{}{})?",
+                            rest,
+                            self.separator(),
+                            components,
+                            self.nonempty()
+                        );
+                        regexes.push((end, INSIDE));
+                    } else {
+                        regexes.push((String::new(), INSIDE));
+                    }
+                    regexes
+                }
+            }
+            Token::Alt(alt) => {
+                let alternation = self.program.alternation(alt);
+                let mut branches: [Vec<String>; 2] = [Vec::new(), Vec::new()];
+                for (branch, &start) in alternation.branches.iter().enumerate() {
+                    let end = alternation
+                        .branches
+                        .get(branch + 1)
+                        .copied()
+                        .unwrap_or(alternation.end);
+                    // Every branch ends with a jump.
+                    let (common, tails) = self.sequence(start..end - 1, position);
+                    for (end, tail) in tails.iter().enumerate() {
+                        if let Some(tail) = tail {
+                            branches[end].push(format!("{}{}", common, tail));
+                        }
+                    }
+                }
+                let [inside, start] = branches;
+                let mut regexes = Vec::new();
+                if !inside.is_empty() {
+                    regexes.push((any_of(inside), INSIDE));
+                }
+                if !start.is_empty() {
+                    regexes.push((any_of(start), START));
+                }
+                regexes
+            }
+            Token::Jump(_) => vec![(String::new(), position)],
+        }
+    }
+
+    /// Returns a regex matching one or more characters within a segment which
+    /// do not start with a dot.
+    fn nonempty(&self) -> String {
+        format!("{}{}*", self.any(true), self.any(false))
+    }
+
+    /// Returns a regex matching one character within a path segment. A dot is
+    /// excluded at the start of a segment.
+    fn any(&self, start: bool) -> String {
+        let dot = start && self.syntax.literal_leading_dot;
+        if self.syntax.separators.is_empty() && !dot {
+            return String::from(".");
+        }
+        let mut class = String::from("[^");
+        if dot {
+            class.push_str(&escape_regex('.'));
+        }
+        self.syntax
+            .separators
+            .iter()
+            .for_each(|&c| class.push_str(&escape_regex(c)));
+        class.push(']');
+        class
+    }
+
+    /// Returns a regex matching any path separator.
+    fn separator(&self) -> String {
+        match self.syntax.separators.as_slice() {
+            [separator] => escape_regex(*separator),
+            separators => {
+                let escaped = separators.iter().map(|&c| escape_regex(c));
+                format!("[{}]", escaped.collect::<String>())
+            }
+        }
+    }
+
+    /// Returns a regex matching one character of the class within a path
+    /// segment, or `None` if the class cannot match any character there.
+    fn class(&self, idx: u32, start: bool) -> Option<String> {
+        let class = self.program.class(idx);
+        // Reversed ranges like `z-a` do not match any character.
+        let ranges = class.ranges().iter().filter(|(from, to)| from <= to);
+        if class.is_negated() {
+            let ranges = ranges.map(|&range| write_range(range)).collect::<String>();
+            // Characters which are never matched are added to the negated class.
+            let any = self.any(start);
+            return Some(match any.strip_prefix("[^") {
+                Some(excluded) => format!("[^{}{}", ranges, excluded),
+                None if ranges.is_empty() => any,
+                None => format!("[^{}]", ranges),
+            });
+        }
+        // Separators and hidden dots are cut out of the ranges, since class
+        // intersections are not supported by every engine.
+        let mut excluded = self.syntax.separators.clone();
+        if start && self.syntax.literal_leading_dot {
+            excluded.push('.');
+        }
+        let ranges = ranges
+            .flat_map(|&range| subtract(range, &excluded))
+            .map(write_range)
+            .collect::<String>();
+        if ranges.is_empty() {
+            None
+        } else {
+            Some(format!("[{}]", ranges))
+        }
+    }
+}
+
+/// Returns the parts of the range which do not contain any of the excluded
+/// characters.
+fn subtract((from, to): (char, char), excluded: &[char]) -> Vec<(char, char)> {
+    let mut parts = vec![(from, to)];
+    for &c in excluded {
+        parts = parts
+            .into_iter()
+            .flat_map(|(from, to)| {
+                if c < from || c > to {
+                    return vec![(from, to)];
+                }
+                let before = prev_char(c).filter(|&before| from <= before);
+                let after = next_char(c).filter(|&after| after <= to);
+                before
+                    .map(|before| (from, before))
+                    .into_iter()
+                    .chain(after.map(|after| (after, to)))
+                    .collect()
+            })
+            .collect();
+    }
+    parts
+}
+
+fn prev_char(c: char) -> Option<char> {
+    match c as u32 {
+        0 => None,
+        0xE000 => Some('\u{D7FF}'),
+        c => char::from_u32(c - 1),
+    }
+}
+
+fn next_char(c: char) -> Option<char> {
+    match c as u32 {
+        0xD7FF => Some('\u{E000}'),
+        c => char::from_u32(c + 1),
+    }
+}
+
+fn write_range((from, to): (char, char)) -> String {
+    if from == to {
+        escape_regex(from)
+    } else if next_char(from) == Some(to) {
+        format!("{}{}", escape_regex(from), escape_regex(to))
+    } else {
+        format!("{}-{}", escape_regex(from), escape_regex(to))
+    }
+}
+
+fn non_empty(regexes: Vec<String>) -> Option<String> {
+    match regexes.len() {
+        0 => None,
+        _ => Some(any_of(regexes)),
+    }
+}
+
+/// Returns a regex matching any of the given regexes.
+fn any_of(mut regexes: Vec<String>) -> String {
+    let len = regexes.len();
+    regexes.retain(|regex| !regex.is_empty());
+    let optional = regexes.len() < len;
+    match (regexes.len(), optional) {
+        (0, _) => String::new(),
+        (1, false) => regexes.remove(0),
+        (_, false) => format!("(?:{})", regexes.join("|")),
+        (_, true) => format!("(?:{})?", regexes.join("|")),
+    }
+}
+
+fn escape_regex(c: char) -> String {
+    if REGEX_META.contains(&c) {
+        format!("\\{}", c)
+    } else {
+        String::from(c)
+    }
+}
+
+/// A pattern for the SQL `LIKE` operator together with its escape character.
+///
+/// Created by [`WildMatchPattern::to_sql_like`](crate::WildMatchPattern::to_sql_like).
+/// Formats as a quoted string literal followed by the `ESCAPE` clause, like
+/// `'100\%%' ESCAPE '\'`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SqlLike {
+    pattern: String,
+    escape: char,
+}
+
+impl SqlLike {
+    /// Returns the `LIKE` pattern, e.g. to pass it as a query parameter.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the escape character of the pattern.
+    pub fn escape(&self) -> char {
+        self.escape
+    }
+}
+
+impl fmt::Display for SqlLike {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut escape = [0; 4];
+        write!(
+            f,
+            "'{}' ESCAPE '{}'",
+            self.pattern.replace('\'', "''"),
+            self.escape.encode_utf8(&mut escape).replace('\'', "''")
+        )
+    }
+}
+
+/// Returns a `LIKE` pattern which matches the same inputs as the program.
+pub(crate) fn to_sql_like(
+    program: &Program,
+    syntax: &Syntax,
+    escape: char,
+) -> Result<SqlLike, TranslateError> {
+    let has_wildcards = program.tokens.iter().any(Token::is_wildcard);
+    if has_wildcards && (!syntax.separators.is_empty() || syntax.literal_leading_dot) {
+        // Wildcards of `LIKE` match every character.
+        return Err(TranslateError::UnsupportedSyntax);
+    }
+    let mut pattern = String::with_capacity(program.literals.len() + program.tokens.len());
+    let push_literal = |pattern: &mut String, c: char| {
+        if c == '%' || c == '_' || c == escape {
+            pattern.push(escape);
+        }
+        pattern.push(c);
+    };
+    for &token in &program.tokens {
+        match token {
+            Token::Multi => pattern.push('%'),
+            Token::Single => pattern.push('_'),
+            Token::Literal(start, end) => program
+                .literal(start, end)
+                .chars()
+                .for_each(|c| push_literal(&mut pattern, c)),
+            // A separator only matches itself if there are no others.
+            Token::Separator(c) if syntax.separators.len() == 1 => push_literal(&mut pattern, c),
+            _ => return Err(TranslateError::UnsupportedSyntax),
+        }
+    }
+    Ok(SqlLike { pattern, escape })
+}
+
+/// Checks that the program can be written as a pattern with the given
+/// wildcards and otherwise the same syntax.
+pub(crate) fn check_wildcards(
+    program: &Program,
+    syntax: &Syntax,
+    multi: char,
+    single: char,
+) -> Result<(), TranslateError> {
+    let is_wildcard = |c: char| c == multi || c == single;
+    if syntax.escape.is_some_and(is_wildcard) || syntax.separators.iter().any(|&c| is_wildcard(c)) {
+        return Err(TranslateError::UnsupportedSyntax);
+    }
+    // Wildcards cannot take the place of class or brace syntax.
+    let is_syntax = |c: char| {
+        (syntax.classes && matches!(c, '[' | ']' | '!'))
+            || (syntax.braces && matches!(c, '{' | ',' | '}'))
+    };
+    if is_syntax(multi) || is_syntax(single) {
+        return Err(TranslateError::UnsupportedSyntax);
+    }
+    if syntax.escape.is_some() {
+        // Rendering escapes every literal wildcard.
+        return Ok(());
+    }
+    match program.tokens.iter().find_map(|&token| match token {
+        Token::Literal(start, end) => program
+            .literal(start, end)
+            .chars()
+            .find(|&c| is_wildcard(c)),
+        _ => None,
+    }) {
+        Some(literal) => Err(TranslateError::EscapeRequired { literal }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::random_string;
+    use crate::{
+        TranslateError, WildMatch, WildMatchBuilder, WildMatchPattern, WildMatchPatternBuilder,
+    };
+    use ntest::{assert_false, test_case};
+    use regex::Regex;
+
+    #[test_case("cat", "^cat$")]
+    #[test_case("c?t*", "(?s)^c.t.*$")]
+    #[test_case("a.b+c(d)", r"^a\.b\+c\(d\)$")]
+    #[test_case("*[*]?", r"(?s)^.*\[.*\].$")]
+    #[test_case("", "^$")]
+    fn to_regex(pattern: &str, expected: &str) {
+        assert_eq!(expected, WildMatch::new(pattern).to_regex());
+    }
+
+    #[test_case("[a-c]?", "(?s)^[a-c].$")]
+    #[test_case("[!]^-]", r"(?s)^[^\]\^\-]$")]
+    #[test_case("{a,b*}.rs", r"(?s)^(?:a|b.*)\.rs$")]
+    fn to_regex_syntax(pattern: &str, expected: &str) {
+        let m = WildMatchBuilder::new()
+            .classes(true)
+            .braces(true)
+            .build(pattern)
+            .unwrap();
+        assert_eq!(expected, m.to_regex());
+    }
+
+    #[test]
+    fn to_regex_path() {
+        let builder = WildMatchBuilder::new().separators(&['/']).classes(true);
+        let m = builder.build("src/**/[!a]*.rs").unwrap();
+        assert_eq!(r"(?s)^src/(?:.*/)?[^a/][^/]*\.rs$", m.to_regex());
+        let m = builder.separators(&['/', '\\']).build("src/?").unwrap();
+        assert_eq!(r"(?s)^src[/\\][^/\\]$", m.to_regex());
+    }
+
+    #[test_case("[a-z]", r"(?s)^[a-z]$")]
+    #[test_case("[--0]", r"(?s)^[\-0]$")]
+    #[test_case("a[--0]", r"(?s)^a[\-\.0]$")]
+    #[test_case("*/[.-0]", r"(?s)^(?:[^\./][^/]*)?/[0]$")]
+    #[test_case("[/]", "(?s)^.^$")]
+    #[test_case("[z-a]", "(?s)^.^$")]
+    #[test_case("{[/],a}", r"(?s)^a$")]
+    fn to_regex_class_without_separators(pattern: &str, expected: &str) {
+        let m = WildMatchBuilder::new()
+            .classes(true)
+            .braces(true)
+            .separators(&['/'])
+            .literal_leading_dot(true)
+            .build(pattern)
+            .unwrap();
+        assert_eq!(expected, m.to_regex());
+        let regex = Regex::new(expected).unwrap();
+        for input in ["a", "-", ".", "/", "0", "a/0", "a/.", "/0"].iter() {
+            assert_eq!(m.matches(input), regex.is_match(input), "{}", input);
+        }
+    }
+
+    #[test]
+    fn to_regex_literal_leading_dot() {
+        let builder = WildMatchBuilder::new().literal_leading_dot(true);
+        assert_eq!(
+            r"(?s)^(?:[^\.].*)?$",
+            builder.build("*").unwrap().to_regex()
+        );
+        assert_eq!(r"(?s)^\..*$", builder.build(".*").unwrap().to_regex());
+        let m = builder.separators(&['/']).build("*/*").unwrap();
+        assert_eq!(r"(?s)^(?:[^\./][^/]*)?/(?:[^\./][^/]*)?$", m.to_regex());
+    }
+
+    #[test]
+    fn to_regex_case_insensitive_non_ascii() {
+        let m = WildMatch::new_case_insensitive("σ");
+        let regex = Regex::new(&m.to_regex()).unwrap();
+        assert!(m.matches("Σ"));
+        assert!(regex.is_match("Σ"));
+        // Differs since the regex folds the final sigma like the others.
+        assert_false!(m.matches("ς"));
+        assert!(regex.is_match("ς"));
+
+        let m = WildMatchBuilder::new()
+            .classes(true)
+            .case_insensitive(true)
+            .build("[a-z]")
+            .unwrap();
+        let regex = Regex::new(&m.to_regex()).unwrap();
+        // Differs since the class matches the lowercase `i` of `İ`.
+        assert!(m.matches("İ"));
+        assert_false!(regex.is_match("İ"));
+    }
+
+    #[test]
+    fn to_regex_matches_like_pattern() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..5_000 {
+            let pattern = random_string(
+                &mut rng,
+                &[
+                    'a', 'B', '.', '/', '*', '?', '[', ']', '!', '-', '{', ',', '}', '\\',
+                ],
+                9,
+            );
+            let flags = rand::random::<u8>();
+            let mut builder = WildMatchBuilder::new()
+                .case_insensitive(flags & 1 != 0)
+                .classes(flags & 2 != 0)
+                .braces(flags & 4 != 0)
+                .literal_leading_dot(flags & 8 != 0)
+                .anchor_start(flags & 16 == 0)
+                .anchor_end(flags & 32 == 0);
+            if flags & 64 != 0 {
+                builder = builder.separators(&['/']);
+            }
+            if flags & 128 != 0 {
+                builder = builder.escape('\\');
+            }
+            let m = match builder.build(&pattern) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            let regex = Regex::new(&m.to_regex()).unwrap();
+            for _ in 0..10 {
+                let input = random_string(&mut rng, &['a', 'b', 'B', '.', '/', '-', '\n'], 7);
+                assert_eq!(
+                    m.matches(&input),
+                    regex.is_match(&input),
+                    "Pattern {:?} ({:?}) as {} on input {:?}",
+                    pattern,
+                    m,
+                    m.to_regex(),
+                    input
+                );
+            }
+        }
+    }
+
+    #[test_case("cat", "cat")]
+    #[test_case("c?t*", "c_t%")]
+    #[test_case("100%", r"100\%")]
+    #[test_case("snake_case", r"snake\_case")]
+    #[test_case(r"C:\*", r"C:\\%")]
+    #[test_case("", "")]
+    fn to_sql_like(pattern: &str, expected: &str) {
+        let like = WildMatch::new(pattern).to_sql_like('\\').unwrap();
+        assert_eq!(expected, like.pattern());
+        assert_eq!('\\', like.escape());
+    }
+
+    #[test]
+    fn to_sql_like_display() {
+        let like = WildMatch::new("it's*").to_sql_like('!').unwrap();
+        assert_eq!("'it''s%' ESCAPE '!'", like.to_string());
+        let like = WildMatch::new("a'b").to_sql_like('\'').unwrap();
+        assert_eq!("a''b", like.pattern());
+        assert_eq!("'a''''b' ESCAPE ''''", like.to_string());
+    }
+
+    #[test]
+    fn to_sql_like_unanchored_and_path() {
+        let builder = WildMatchBuilder::new().anchor_start(false);
+        let like = builder.build("c?t").unwrap().to_sql_like('\\').unwrap();
+        assert_eq!("%c_t", like.pattern());
+        let m = builder.separators(&['/']).build("src/lib.rs").unwrap();
+        assert_eq!(Err(TranslateError::UnsupportedSyntax), m.to_sql_like('\\'));
+        let m = WildMatchBuilder::new()
+            .separators(&['/'])
+            .build("src/lib.rs")
+            .unwrap();
+        assert_eq!("src/lib.rs", m.to_sql_like('\\').unwrap().pattern());
+    }
+
+    #[test_case("[ab]")]
+    #[test_case("{a,b}")]
+    #[test_case("src/*")]
+    #[test_case(".*")]
+    fn to_sql_like_unsupported(pattern: &str) {
+        let m = WildMatchBuilder::new()
+            .classes(true)
+            .braces(true)
+            .separators(&['/', '\\'])
+            .literal_leading_dot(pattern.starts_with('.'))
+            .build(pattern)
+            .unwrap();
+        assert_eq!(Err(TranslateError::UnsupportedSyntax), m.to_sql_like('\\'));
+    }
+
+    #[test_case(r"100\%%", "100% done", true)]
+    #[test_case(r"100\%%", "1000", false)]
+    #[test_case(r"a\_b", "a_b", true)]
+    #[test_case(r"a\_b", "axb", false)]
+    #[test_case(r"a\\b", r"a\b", true)]
+    fn from_sql_like(pattern: &str, input: &str, expected: bool) {
+        let m = WildMatchPattern::from_sql_like(pattern, Some('\\')).unwrap();
+        assert_eq!(expected, m.matches(input));
+    }
+
+    #[test]
+    fn from_sql_like_without_escape() {
+        let m = WildMatchPattern::from_sql_like(r"a\_", None).unwrap();
+        assert!(m.matches(r"a\b"));
+        assert_false!(m.matches("a_"));
+        assert!(WildMatchPattern::from_sql_like(r"a\", Some('\\')).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "escape character cannot be a wildcard")]
+    fn to_sql_like_escape_is_wildcard() {
+        let _ = WildMatch::new("").to_sql_like('%');
+    }
+
+    #[test]
+    fn sql_like_roundtrip() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1_000 {
+            let pattern = random_string(&mut rng, &['a', '*', '?', '%', '_', '!'], 7);
+            let m = WildMatch::new(&pattern);
+            let like = m.to_sql_like('!').unwrap();
+            let sql = WildMatchPattern::from_sql_like(like.pattern(), Some('!')).unwrap();
+            for _ in 0..10 {
+                let input = random_string(&mut rng, &['a', '%', '_', '!'], 7);
+                assert_eq!(
+                    m.matches(&input),
+                    sql.matches(&input),
+                    "Pattern {:?} as {} on input {:?}",
+                    pattern,
+                    like,
+                    input
+                );
+            }
+        }
+    }
+
+    #[test_case("c?t*", "c_t%")]
+    #[test_case(r"100%\*", r"100\%*")]
+    #[test_case(r"a\?_", r"a?\_")]
+    #[test_case(r"\\", r"\\")]
+    fn to_dialect(pattern: &str, expected: &str) {
+        let m = WildMatch::new_with_escape(pattern, '\\');
+        let sql = m.to_dialect::<'%', '_'>().unwrap();
+        assert_eq!(expected, sql.pattern());
+        assert_eq!(m, sql.to_dialect().unwrap());
+    }
+
+    #[test]
+    fn to_dialect_keeps_syntax() {
+        let m = WildMatchBuilder::new()
+            .case_insensitive(true)
+            .classes(true)
+            .braces(true)
+            .separators(&['/'])
+            .anchor_start(false)
+            .build("src/**/[a-c]*.{RS,TOML}")
+            .unwrap();
+        let m = m.to_dialect::<'%', '_'>().unwrap();
+        assert_eq!("src/%%/[a-c]%.{RS,TOML}", m.pattern());
+        assert!(m.is_case_insensitive());
+        assert_false!(m.is_anchored_start());
+        assert!(m.matches("crate/src/x/b.toml"));
+        assert_false!(m.matches("crate/src/x/d.toml"));
+    }
+
+    #[test]
+    fn to_dialect_errors() {
+        assert_eq!(
+            Err(TranslateError::EscapeRequired { literal: '_' }),
+            WildMatch::new("snake_case").to_dialect::<'%', '_'>()
+        );
+        assert_eq!(
+            Err(TranslateError::UnsupportedSyntax),
+            WildMatch::new_with_escape("a", '%').to_dialect::<'%', '_'>()
+        );
+        assert_eq!(
+            Err(TranslateError::UnsupportedSyntax),
+            WildMatch::new_with_separators("a", &['_']).to_dialect::<'%', '_'>()
+        );
+        assert!(WildMatch::new("100%").to_dialect::<'#', '_'>().is_ok());
+    }
+
+    #[test]
+    fn to_dialect_wildcard_is_syntax() {
+        let classes = WildMatch::new_with_classes("*[!a]");
+        assert_eq!(
+            Err(TranslateError::UnsupportedSyntax),
+            classes.to_dialect::<'[', '_'>()
+        );
+        assert_eq!(
+            Err(TranslateError::UnsupportedSyntax),
+            classes.to_dialect::<'%', ']'>()
+        );
+        assert_eq!(
+            Err(TranslateError::UnsupportedSyntax),
+            classes.to_dialect::<'%', '!'>()
+        );
+        let braces = WildMatch::new_with_braces("*{a,b}");
+        assert_eq!(
+            Err(TranslateError::UnsupportedSyntax),
+            braces.to_dialect::<'{', '_'>()
+        );
+        assert_eq!(
+            Err(TranslateError::UnsupportedSyntax),
+            braces.to_dialect::<'%', ','>()
+        );
+        assert_eq!(
+            Err(TranslateError::UnsupportedSyntax),
+            braces.to_dialect::<'}', '_'>()
+        );
+        // Without the syntax, they are plain characters.
+        assert!(WildMatch::new("*a").to_dialect::<'{', '!'>().is_ok());
+    }
+
+    #[test]
+    fn to_dialect_matches_like_pattern() {
+        let mut rng = rand::thread_rng();
+        let builder = WildMatchPatternBuilder::<'%', '_'>::new().escape('\\');
+        for _ in 0..1_000 {
+            let pattern = random_string(&mut rng, &['a', '*', '?', '%', '_', '\\'], 7);
+            let m = WildMatch::new_with_escape(&pattern, '\\');
+            let sql = m.to_dialect::<'%', '_'>().unwrap();
+            assert_eq!(Ok(&sql), builder.build(sql.pattern()).as_ref());
+            for _ in 0..10 {
+                let input = random_string(&mut rng, &['a', '*', '?', '%', '_', '\\'], 7);
+                assert_eq!(
+                    m.matches(&input),
+                    sql.matches(&input),
+                    "Pattern {:?} as {:?} on input {:?}",
+                    pattern,
+                    sql.pattern(),
+                    input
+                );
+            }
+        }
+    }
+}